@@ -0,0 +1,123 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::Serialize;
+use tauri_plugin_http::reqwest::{self, StatusCode};
+
+/// Sent to the frontend as `{ "kind": "...", "detail": ... }`
+#[derive(Debug, Clone, PartialEq, Serialize, thiserror::Error)]
+#[serde(tag = "kind", content = "detail")]
+pub enum AuthError {
+    #[error("Network error: {0}")]
+    Network(String),
+    #[error("Too many requests, please try again later")]
+    RateLimited,
+    #[error("Unexpected response: {0}")]
+    InvalidResponse(String),
+    /// The authorization code or the refresh token is invalid or expired
+    #[error("The sign in has expired, please sign in again")]
+    InvalidGrant,
+    /// The `errorMessage` of a Yggdrasil server, e.g. for a wrong password
+    #[error("{0}")]
+    Rejected(String),
+    #[error("The device code has expired")]
+    DeviceCodeExpired,
+    #[error("The sign in has been declined")]
+    DeviceCodeDeclined,
+    #[error("This Microsoft account doesn't have an Xbox profile")]
+    NoXboxAccount,
+    #[error("Xbox Live is not available in your country or region")]
+    XboxLiveUnavailable,
+    #[error("The account needs adult verification on the Xbox website")]
+    AdultVerificationRequired,
+    #[error("This is a child account, it must be added to a family by an adult")]
+    ChildAccount,
+    /// Other `XErr` codes of XSTS
+    #[error("Xbox Live authentication failed with error {0}")]
+    Xsts(u64),
+    #[error("This account doesn't own Minecraft")]
+    NoEntitlement,
+    /// The game is owned, but the player name hasn't been set
+    #[error("This account doesn't have a Minecraft profile")]
+    NoProfile,
+    #[error("This account has already been added")]
+    AlreadyAdded,
+    #[error("The account has not been added")]
+    AccountNotFound,
+    #[error("Failed to save the account: {0}")]
+    Storage(String),
+}
+
+impl AuthError {
+    pub fn from_xerr(xerr: u64) -> Self {
+        match xerr {
+            2148916233 => Self::NoXboxAccount,
+            2148916235 => Self::XboxLiveUnavailable,
+            2148916236 | 2148916237 => Self::AdultVerificationRequired,
+            2148916238 => Self::ChildAccount,
+            x => Self::Xsts(x),
+        }
+    }
+}
+
+impl From<reqwest::Error> for AuthError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.status() == Some(StatusCode::TOO_MANY_REQUESTS) {
+            Self::RateLimited
+        } else if err.is_decode() {
+            Self::InvalidResponse(err.to_string())
+        } else {
+            Self::Network(err.to_string())
+        }
+    }
+}
+
+impl From<serde_json::Error> for AuthError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::InvalidResponse(err.to_string())
+    }
+}
+
+/// Errors of the Minecraft profile API, sent to the frontend like [`AuthError`]
+#[derive(Debug, Clone, PartialEq, Serialize, thiserror::Error)]
+#[serde(tag = "kind", content = "detail")]
+pub enum ProfileError {
+    #[error("Network error: {0}")]
+    Network(String),
+    #[error("Too many requests, please try again later")]
+    RateLimited,
+    #[error("The access token has expired, please refresh the account")]
+    Unauthorized,
+    #[error("Unexpected response: {0}")]
+    InvalidResponse(String),
+    #[error("Invalid skin: {0}")]
+    InvalidSkin(String),
+    #[error("Invalid player name")]
+    InvalidName,
+    /// With the status returned by the server, `DUPLICATE` or `NOT_ALLOWED`
+    #[error("The name is not available: {0}")]
+    NameUnavailable(String),
+    #[error("Only Microsoft accounts have a Minecraft profile")]
+    NotMicrosoftAccount,
+    #[error("The account has not been added")]
+    AccountNotFound,
+    #[error("Failed to save the account: {0}")]
+    Storage(String),
+}
+
+impl From<reqwest::Error> for ProfileError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_decode() {
+            Self::InvalidResponse(err.to_string())
+        } else {
+            Self::Network(err.to_string())
+        }
+    }
+}
+
+impl From<serde_json::Error> for ProfileError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::InvalidResponse(err.to_string())
+    }
+}
@@ -0,0 +1,662 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Sign in through Microsoft, Xbox Live and Minecraft services
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri_plugin_http::reqwest::{self, StatusCode};
+
+use super::{
+    texture::{load_texture, texture_key, texture_url},
+    Account, AccountType, AuthError, Profile, Skin,
+};
+use crate::HTTP_CLIENT;
+
+/// Base urls of the services, tests replace them with a mock server
+pub struct Endpoints {
+    pub live: String,
+    pub xbox_user: String,
+    pub xsts: String,
+    pub minecraft: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            live: "https://login.live.com".to_string(),
+            xbox_user: "https://user.auth.xboxlive.com".to_string(),
+            xsts: "https://xsts.auth.xboxlive.com".to_string(),
+            minecraft: "https://api.minecraftservices.com".to_string(),
+        }
+    }
+}
+
+pub(super) static ENDPOINTS: Lazy<Endpoints> = Lazy::new(Endpoints::default);
+
+pub enum LoginPayload {
+    RefreshToken(String),
+    AccessCode(String),
+    DeviceCode(DeviceCode),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DeviceCode {
+    pub user_code: String,
+    pub device_code: String,
+    pub verification_uri: String,
+    /// Seconds before the codes expire
+    pub expires_in: u64,
+    /// Seconds to wait between polling requests
+    pub interval: u64,
+}
+
+struct MicrosoftToken {
+    access_token: String,
+    refresh_token: String,
+    expires_in: u64,
+}
+
+/// Login or refresh login.
+///
+/// Note: Tokens are only saved through [`super::storage`], which encrypts them
+pub async fn microsoft_login(payload: LoginPayload) -> Result<Account, AuthError> {
+    login(payload, &ENDPOINTS).await
+}
+
+pub async fn request_device_code() -> Result<DeviceCode, AuthError> {
+    Ok(send(
+        HTTP_CLIENT
+            .post(format!("{}/oauth20_connect.srf", ENDPOINTS.live))
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(
+                "client_id=00000000402b5328".to_string()
+                    + "&response_type=device_code"
+                    + "&scope=service::user.auth.xboxlive.com::MBI_SSL",
+            ),
+    )
+    .await?
+    .error_for_status()?
+    .json()
+    .await?)
+}
+
+async fn login(payload: LoginPayload, endpoints: &Endpoints) -> Result<Account, AuthError> {
+    let token = match payload {
+        LoginPayload::RefreshToken(token) => {
+            get_access_token_from_refresh_token(&token, endpoints).await?
+        }
+        LoginPayload::AccessCode(code) => get_access_token(&code, endpoints).await?,
+        LoginPayload::DeviceCode(device_code) => poll_device_code(&device_code, endpoints).await?,
+    };
+    info!("Successfully get Microsoft access token");
+    let xbox_auth_response = xbox_authenticate(&token.access_token, endpoints).await?;
+    info!("Successfully login Xbox");
+    let xsts_token = xsts_authenticate(&xbox_auth_response.xbl_token, endpoints).await?;
+    info!("Successfully verify XSTS");
+    let minecraft_access_token =
+        minecraft_authenticate(&xbox_auth_response.xbl_uhs, &xsts_token, endpoints).await?;
+    info!("Successfully get Minecraft access token");
+    check_game(&minecraft_access_token, endpoints).await?;
+    info!("Successfully check ownership");
+    let player_info = get_player_infomations(&minecraft_access_token, endpoints).await?;
+    info!("Successfully get game profile");
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    Ok(Account {
+        refresh_token: Some(token.refresh_token),
+        access_token: Some(minecraft_access_token),
+        token_deadline: Some(now.as_secs() + token.expires_in),
+        client_token: None,
+        profile: parse_profile(&player_info).await?,
+        account_type: AccountType::Microsoft,
+    })
+}
+
+/// Parse the response of the Minecraft profile API
+pub(super) async fn parse_profile(player_info: &Value) -> serde_json::Result<Profile> {
+    Ok(Profile {
+        profile_name: serde_json::from_value(player_info["name"].clone())?,
+        uuid: serde_json::from_value(player_info["id"].clone())?,
+        skins: resolve_skins(serde_json::from_value(player_info["skins"].clone())?).await,
+        capes: serde_json::from_value(player_info["capes"].clone())?,
+    })
+}
+
+/// Send the request and turn HTTP 429 into [`AuthError::RateLimited`]
+async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response, AuthError> {
+    let response = request.send().await?;
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+        return Err(AuthError::RateLimited);
+    }
+    Ok(response)
+}
+
+fn unexpected_status(service: &str, status: StatusCode) -> AuthError {
+    AuthError::InvalidResponse(format!("{service} returned {status}"))
+}
+
+async fn request_token(body: String, endpoints: &Endpoints) -> Result<Value, AuthError> {
+    Ok(send(
+        HTTP_CLIENT
+            .post(format!("{}/oauth20_token.srf", endpoints.live))
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(body),
+    )
+    .await?
+    .json()
+    .await?)
+}
+
+fn parse_token(response: Value) -> Result<MicrosoftToken, AuthError> {
+    match response["error"].as_str() {
+        None => (),
+        Some("invalid_grant") => return Err(AuthError::InvalidGrant),
+        Some("expired_token") => return Err(AuthError::DeviceCodeExpired),
+        Some("authorization_declined" | "access_denied") => {
+            return Err(AuthError::DeviceCodeDeclined)
+        }
+        Some(error) => return Err(AuthError::InvalidResponse(error.to_string())),
+    }
+    let field = |name: &str| {
+        response[name]
+            .as_str()
+            .map(str::to_string)
+            .ok_or(AuthError::InvalidResponse(format!("No {name}")))
+    };
+    Ok(MicrosoftToken {
+        access_token: field("access_token")?,
+        refresh_token: field("refresh_token")?,
+        expires_in: response["expires_in"]
+            .as_u64()
+            .ok_or(AuthError::InvalidResponse("No expires_in".to_string()))?,
+    })
+}
+
+async fn get_access_token(code: &str, endpoints: &Endpoints) -> Result<MicrosoftToken, AuthError> {
+    let body = "client_id=00000000402b5328".to_string()
+        + "&grant_type=authorization_code"
+        + "&code="
+        + code
+        + "&redirect_uri=https://login.live.com/oauth20_desktop.srf"
+        + "&scope=service::user.auth.xboxlive.com::MBI_SSL";
+    parse_token(request_token(body, endpoints).await?)
+}
+
+async fn get_access_token_from_refresh_token(
+    refresh_token: &str,
+    endpoints: &Endpoints,
+) -> Result<MicrosoftToken, AuthError> {
+    let body = "client_id=00000000402b5328".to_string()
+        + "&grant_type=refresh_token"
+        + "&refresh_token="
+        + refresh_token
+        + "&redirect_uri=https://login.live.com/oauth20_desktop.srf"
+        + "&scope=service::user.auth.xboxlive.com::MBI_SSL";
+    parse_token(request_token(body, endpoints).await?)
+}
+
+/// Poll the token endpoint until the user finishes signing in
+async fn poll_device_code(
+    device_code: &DeviceCode,
+    endpoints: &Endpoints,
+) -> Result<MicrosoftToken, AuthError> {
+    let deadline = Instant::now() + Duration::from_secs(device_code.expires_in);
+    let mut interval = device_code.interval.max(1);
+    loop {
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+        if Instant::now() > deadline {
+            return Err(AuthError::DeviceCodeExpired);
+        }
+        let body = "client_id=00000000402b5328".to_string()
+            + "&grant_type=urn:ietf:params:oauth:grant-type:device_code"
+            + "&device_code="
+            + &device_code.device_code;
+        let response = request_token(body, endpoints).await?;
+        match response["error"].as_str() {
+            Some("authorization_pending") => (),
+            Some("slow_down") => interval += 5,
+            _ => return parse_token(response),
+        }
+    }
+}
+
+struct XboxAuth {
+    xbl_token: String,
+    xbl_uhs: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct XboxAuthProperties {
+    #[serde(rename = "AuthMethod")]
+    auth_method: String,
+    #[serde(rename = "SiteName")]
+    site_name: String,
+    #[serde(rename = "RpsTicket")]
+    rps_ticket: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct XboxAuthBody {
+    #[serde(rename = "Properties")]
+    properties: XboxAuthProperties,
+    #[serde(rename = "RelyingParty")]
+    relying_party: String,
+    #[serde(rename = "TokenType")]
+    token_type: String,
+}
+
+impl XboxAuthBody {
+    fn new(access_token: &str) -> Self {
+        Self {
+            properties: XboxAuthProperties {
+                auth_method: "RPS".to_string(),
+                site_name: "user.auth.xboxlive.com".to_string(),
+                rps_ticket: access_token.to_string(),
+            },
+            relying_party: "http://auth.xboxlive.com".to_string(),
+            token_type: "JWT".to_string(),
+        }
+    }
+}
+
+async fn xbox_authenticate(
+    access_token: &str,
+    endpoints: &Endpoints,
+) -> Result<XboxAuth, AuthError> {
+    let response = send(
+        HTTP_CLIENT
+            .post(format!("{}/user/authenticate", endpoints.xbox_user))
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json")
+            .body(serde_json::to_string(&XboxAuthBody::new(access_token))?),
+    )
+    .await?;
+    if !response.status().is_success() {
+        return Err(unexpected_status("Xbox Live", response.status()));
+    }
+    let response: Value = response.json().await?;
+    Ok(XboxAuth {
+        xbl_token: response["Token"]
+            .as_str()
+            .ok_or(AuthError::InvalidResponse("No XBL Token".to_string()))?
+            .to_string(),
+        xbl_uhs: response["DisplayClaims"]["xui"][0]["uhs"]
+            .as_str()
+            .ok_or(AuthError::InvalidResponse("No XBL UHS".to_string()))?
+            .to_string(),
+    })
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct XSTSAuthProperties {
+    #[serde(rename = "SandboxId")]
+    sandbox_id: String,
+    #[serde(rename = "UserTokens")]
+    user_tokens: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct XSTSAuthBody {
+    #[serde(rename = "Properties")]
+    properties: XSTSAuthProperties,
+    #[serde(rename = "RelyingParty")]
+    relying_party: String,
+    #[serde(rename = "TokenType")]
+    token_type: String,
+}
+
+impl XSTSAuthBody {
+    fn new(xbl_token: &str) -> Self {
+        Self {
+            properties: XSTSAuthProperties {
+                sandbox_id: "RETAIL".to_string(),
+                user_tokens: vec![xbl_token.to_string()],
+            },
+            relying_party: "rp://api.minecraftservices.com/".to_string(),
+            token_type: "JWT".to_string(),
+        }
+    }
+}
+
+async fn xsts_authenticate(xbl_token: &str, endpoints: &Endpoints) -> Result<String, AuthError> {
+    let response = send(
+        HTTP_CLIENT
+            .post(format!("{}/xsts/authorize", endpoints.xsts))
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json")
+            .body(serde_json::to_string(&XSTSAuthBody::new(xbl_token))?),
+    )
+    .await?;
+    let status = response.status();
+    if status == StatusCode::UNAUTHORIZED {
+        let response: Value = response.json().await?;
+        return Err(match response["XErr"].as_u64() {
+            Some(xerr) => AuthError::from_xerr(xerr),
+            None => unexpected_status("XSTS", status),
+        });
+    }
+    if !status.is_success() {
+        return Err(unexpected_status("XSTS", status));
+    }
+    let response: Value = response.json().await?;
+    Ok(response["Token"]
+        .as_str()
+        .ok_or(AuthError::InvalidResponse("No XSTS token".to_string()))?
+        .to_string())
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct MinecraftAuthBody {
+    #[serde(rename = "identityToken")]
+    identity_token: String,
+}
+
+impl MinecraftAuthBody {
+    fn new(xbl_uhs: &str, xsts_token: &str) -> Self {
+        Self {
+            identity_token: format!("XBL3.0 x={xbl_uhs}; {xsts_token}"),
+        }
+    }
+}
+
+async fn minecraft_authenticate(
+    xbl_uhs: &str,
+    xsts_token: &str,
+    endpoints: &Endpoints,
+) -> Result<String, AuthError> {
+    let response = send(
+        HTTP_CLIENT
+            .post(format!(
+                "{}/authentication/login_with_xbox",
+                endpoints.minecraft
+            ))
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json")
+            .body(serde_json::to_string(&MinecraftAuthBody::new(
+                xbl_uhs, xsts_token,
+            ))?),
+    )
+    .await?;
+    if !response.status().is_success() {
+        return Err(unexpected_status("Minecraft services", response.status()));
+    }
+    let response: Value = response.json().await?;
+    Ok(response["access_token"]
+        .as_str()
+        .ok_or(AuthError::InvalidResponse("No Access Token".to_string()))?
+        .to_string())
+}
+
+async fn check_game(minecraft_access_token: &str, endpoints: &Endpoints) -> Result<(), AuthError> {
+    let response = send(
+        HTTP_CLIENT
+            .get(format!("{}/entitlements/mcstore", endpoints.minecraft))
+            .header("Content-Type", "application/json")
+            .header("Authorization", format!("Bearer {minecraft_access_token}")),
+    )
+    .await?;
+    if !response.status().is_success() {
+        return Err(unexpected_status("Minecraft services", response.status()));
+    }
+    let response: Value = response.json().await?;
+    match response["items"].as_array() {
+        Some(items) if !items.is_empty() => Ok(()),
+        _ => Err(AuthError::NoEntitlement),
+    }
+}
+
+async fn get_player_infomations(
+    minecraft_access_token: &str,
+    endpoints: &Endpoints,
+) -> Result<Value, AuthError> {
+    let response = send(
+        HTTP_CLIENT
+            .get(format!("{}/minecraft/profile", endpoints.minecraft))
+            .header("Content-Type", "application/json")
+            .header("Authorization", format!("Bearer {minecraft_access_token}")),
+    )
+    .await?;
+    match response.status() {
+        StatusCode::NOT_FOUND => Err(AuthError::NoProfile),
+        status if !status.is_success() => Err(unexpected_status("Minecraft services", status)),
+        _ => Ok(response.json().await?),
+    }
+}
+
+/// Whether Minecraft services still accept the access token
+pub async fn probe_profile(minecraft_access_token: &str) -> Result<bool, AuthError> {
+    check_access_token(minecraft_access_token, &ENDPOINTS).await
+}
+
+async fn check_access_token(
+    minecraft_access_token: &str,
+    endpoints: &Endpoints,
+) -> Result<bool, AuthError> {
+    let response = send(
+        HTTP_CLIENT
+            .get(format!("{}/minecraft/profile", endpoints.minecraft))
+            .header("Authorization", format!("Bearer {minecraft_access_token}")),
+    )
+    .await?;
+    match response.status() {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Ok(false),
+        StatusCode::NOT_FOUND => Err(AuthError::NoProfile),
+        status if !status.is_success() => Err(unexpected_status("Minecraft services", status)),
+        _ => Ok(true),
+    }
+}
+
+/// Download the textures into the cache, the account only keeps their keys and urls
+async fn resolve_skins(mut skins: Vec<Skin>) -> Vec<Skin> {
+    for skin in &mut skins {
+        let Some(key) = texture_key(&skin.url).map(str::to_string) else {
+            continue;
+        };
+        if let Err(err) = load_texture(&skin.url, &key).await {
+            warn!("Failed to download the skin {key}: {err}");
+        }
+        skin.url = texture_url(&key);
+        skin.texture_key = key;
+    }
+    skins
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::utils::mock_server::{MockServer, Response};
+
+    #[derive(Clone, Copy, Default)]
+    struct Behavior {
+        xerr: Option<u64>,
+        rate_limited: bool,
+        owns_game: bool,
+        has_profile: bool,
+    }
+
+    fn mock_services(behavior: Behavior) -> (MockServer, Endpoints) {
+        let server = MockServer::start(move |request| match request.path.as_str() {
+            "/oauth20_token.srf" => {
+                if request.body.contains("refresh_token=expired") {
+                    return Response::json(400, json!({ "error": "invalid_grant" }));
+                }
+                assert!(request.body.contains("code=the-code"));
+                Response::json(
+                    200,
+                    json!({
+                        "access_token": "ms-token",
+                        "refresh_token": "ms-refresh",
+                        "expires_in": 3600,
+                    }),
+                )
+            }
+            "/user/authenticate" => {
+                assert_eq!(request.json()["Properties"]["RpsTicket"], "ms-token");
+                Response::json(
+                    200,
+                    json!({ "Token": "xbl", "DisplayClaims": { "xui": [{ "uhs": "uhs" }] } }),
+                )
+            }
+            "/xsts/authorize" => match behavior.xerr {
+                Some(xerr) => Response::json(401, json!({ "Identity": "0", "XErr": xerr })),
+                None => Response::json(200, json!({ "Token": "xsts" })),
+            },
+            "/authentication/login_with_xbox" => {
+                if behavior.rate_limited {
+                    return Response::new(429, "");
+                }
+                assert_eq!(request.json()["identityToken"], "XBL3.0 x=uhs; xsts");
+                Response::json(200, json!({ "access_token": "mc-token" }))
+            }
+            "/entitlements/mcstore" => {
+                let items = if behavior.owns_game {
+                    json!([{ "name": "game_minecraft" }])
+                } else {
+                    json!([])
+                };
+                Response::json(200, json!({ "items": items }))
+            }
+            "/minecraft/profile" => {
+                let authorized = request
+                    .headers
+                    .iter()
+                    .any(|(name, value)| name == "authorization" && value == "Bearer mc-token");
+                if !authorized {
+                    return Response::new(401, "");
+                }
+                if !behavior.has_profile {
+                    return Response::json(404, json!({ "error": "NOT_FOUND" }));
+                }
+                Response::json(
+                    200,
+                    json!({ "id": "uuid", "name": "Steve", "skins": [], "capes": [] }),
+                )
+            }
+            _ => Response::new(404, ""),
+        });
+        let endpoints = Endpoints {
+            live: server.url.clone(),
+            xbox_user: server.url.clone(),
+            xsts: server.url.clone(),
+            minecraft: server.url.clone(),
+        };
+        (server, endpoints)
+    }
+
+    fn normal() -> Behavior {
+        Behavior {
+            owns_game: true,
+            has_profile: true,
+            ..Default::default()
+        }
+    }
+
+    async fn login_with_code(behavior: Behavior) -> Result<Account, AuthError> {
+        let (_server, endpoints) = mock_services(behavior);
+        login(LoginPayload::AccessCode("the-code".to_string()), &endpoints).await
+    }
+
+    #[tokio::test]
+    async fn login_successfully() {
+        let account = login_with_code(normal()).await.unwrap();
+        assert_eq!(account.profile.profile_name, "Steve");
+        assert_eq!(account.access_token.as_deref(), Some("mc-token"));
+        assert_eq!(account.refresh_token.as_deref(), Some("ms-refresh"));
+        assert!(account.token_deadline.is_some());
+    }
+
+    #[tokio::test]
+    async fn xsts_errors() {
+        for (xerr, error) in [
+            (2148916233, AuthError::NoXboxAccount),
+            (2148916235, AuthError::XboxLiveUnavailable),
+            (2148916238, AuthError::ChildAccount),
+            (2148916227, AuthError::Xsts(2148916227)),
+        ] {
+            let behavior = Behavior {
+                xerr: Some(xerr),
+                ..normal()
+            };
+            assert_eq!(login_with_code(behavior).await.err(), Some(error));
+        }
+    }
+
+    #[tokio::test]
+    async fn rate_limited() {
+        let behavior = Behavior {
+            rate_limited: true,
+            ..normal()
+        };
+        assert_eq!(
+            login_with_code(behavior).await.err(),
+            Some(AuthError::RateLimited)
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_entitlement_and_profile() {
+        let behavior = Behavior {
+            owns_game: false,
+            ..normal()
+        };
+        assert_eq!(
+            login_with_code(behavior).await.err(),
+            Some(AuthError::NoEntitlement)
+        );
+        let behavior = Behavior {
+            has_profile: false,
+            ..normal()
+        };
+        assert_eq!(
+            login_with_code(behavior).await.err(),
+            Some(AuthError::NoProfile)
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_refresh_token() {
+        let (_server, endpoints) = mock_services(normal());
+        let result = login(
+            LoginPayload::RefreshToken("expired".to_string()),
+            &endpoints,
+        )
+        .await;
+        assert_eq!(result.err(), Some(AuthError::InvalidGrant));
+    }
+
+    #[tokio::test]
+    async fn probe_access_token() {
+        let (_server, endpoints) = mock_services(normal());
+        assert_eq!(check_access_token("mc-token", &endpoints).await, Ok(true));
+        assert_eq!(check_access_token("expired", &endpoints).await, Ok(false));
+    }
+
+    #[tokio::test]
+    async fn network_error() {
+        let endpoints = Endpoints {
+            live: "http://127.0.0.1:1".to_string(),
+            ..Default::default()
+        };
+        let result = login(LoginPayload::AccessCode("the-code".to_string()), &endpoints).await;
+        assert!(matches!(result, Err(AuthError::Network(_))));
+    }
+
+    #[test]
+    fn error_is_sent_with_kind() {
+        assert_eq!(
+            serde_json::to_value(AuthError::Xsts(1)).unwrap(),
+            json!({ "kind": "Xsts", "detail": 1 })
+        );
+        assert_eq!(
+            serde_json::to_value(AuthError::ChildAccount).unwrap(),
+            json!({ "kind": "ChildAccount" })
+        );
+    }
+}
@@ -0,0 +1,366 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use log::{error, info, warn};
+use md5::{Digest, Md5};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::event::{Event, EventSink, TauriEventSink};
+
+mod error;
+pub mod microsoft;
+pub mod profile;
+pub mod service;
+mod storage;
+pub mod texture;
+pub mod yggdrasil;
+
+pub use error::{AuthError, ProfileError};
+pub use microsoft::{
+    microsoft_login, probe_profile, request_device_code, DeviceCode, LoginPayload,
+};
+pub use service::ACCOUNTS;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Skin {
+    pub id: String,
+    pub state: String,
+    #[serde(rename(serialize = "textureKey", deserialize = "textureKey"))]
+    pub texture_key: String,
+    /// Where the texture is downloaded from, the texture itself is kept in the cache
+    pub url: String,
+    pub variant: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Cape {
+    pub alias: String,
+    pub id: String,
+    pub state: String,
+    pub url: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub profile_name: String,
+    pub uuid: String,
+    pub skins: Vec<Skin>,
+    pub capes: Vec<Cape>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub enum AccountType {
+    Microsoft,
+    Offline,
+    /// Third-party authentication server, `server` is the API root
+    Yggdrasil {
+        server: String,
+    },
+}
+
+impl AccountType {
+    /// Value of the `${user_type}` game argument
+    pub fn user_type(&self) -> &'static str {
+        match self {
+            Self::Microsoft => "msa",
+            Self::Offline => "legacy",
+            Self::Yggdrasil { .. } => "mojang",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub refresh_token: Option<String>,
+    pub access_token: Option<String>,
+    pub token_deadline: Option<u64>,
+    /// Only used by Yggdrasil accounts
+    #[serde(default)]
+    pub client_token: Option<String>,
+    pub profile: Profile,
+    pub account_type: AccountType,
+}
+
+#[tauri::command]
+pub fn get_accounts() -> Result<Vec<Account>, ()> {
+    ACCOUNTS.list().map_err(|err| {
+        error!("Failed to read the accounts: {err}");
+    })
+}
+
+#[tauri::command]
+pub fn get_account_by_uuid(uuid: &str) -> Vec<Account> {
+    get_accounts()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|x| x.profile.uuid == uuid)
+        .collect()
+}
+
+fn add_account(account: Account, sink: &dyn EventSink) -> anyhow::Result<()> {
+    ACCOUNTS.modify(|accounts| {
+        if accounts
+            .iter()
+            .any(|x| x.profile.uuid == account.profile.uuid)
+        {
+            return Err(anyhow!("This account has already been added"));
+        }
+        accounts.push(account);
+        Ok(())
+    })?;
+    sink.emit(Event::AccountsChanged);
+    Ok(())
+}
+
+/// Replace the account with the same uuid
+fn update_account(account: Account, sink: &dyn EventSink) -> anyhow::Result<()> {
+    if !ACCOUNTS.replace(account)? {
+        return Err(anyhow!("The account has been deleted"));
+    }
+    sink.emit(Event::AccountsChanged);
+    Ok(())
+}
+
+#[tauri::command(async)]
+pub async fn delete_account(uuid: String) -> Result<(), ()> {
+    ACCOUNTS
+        .modify(|accounts| {
+            accounts.retain(|x| x.profile.uuid != uuid);
+            Ok(())
+        })
+        .map_err(|err| {
+            error!("Failed to delete the account: {err}");
+        })?;
+    TauriEventSink.emit(Event::AccountsChanged);
+    Ok(())
+}
+
+static PLAYER_NAME_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z0-9_]{3,16}$").unwrap());
+
+/// Same as the server in offline mode: `UUID.nameUUIDFromBytes("OfflinePlayer:<name>")`
+pub fn offline_uuid(name: &str) -> Uuid {
+    let digest = Md5::digest(format!("OfflinePlayer:{name}"));
+    uuid::Builder::from_md5_bytes(digest.into()).into_uuid()
+}
+
+pub fn is_valid_player_name(name: &str) -> bool {
+    PLAYER_NAME_REGEX.is_match(name)
+}
+
+pub fn offline_account(name: &str) -> anyhow::Result<Account> {
+    if !is_valid_player_name(name) {
+        return Err(anyhow!("Invalid player name: {name}"));
+    }
+    Ok(Account {
+        refresh_token: None,
+        access_token: None,
+        token_deadline: None,
+        client_token: None,
+        profile: Profile {
+            profile_name: name.to_string(),
+            uuid: offline_uuid(name).simple().to_string(),
+            skins: vec![],
+            capes: vec![],
+        },
+        account_type: AccountType::Offline,
+    })
+}
+
+#[tauri::command(async)]
+pub async fn add_offline_account(name: String) -> std::result::Result<(), ()> {
+    fn add_offline_account(name: &str) -> anyhow::Result<()> {
+        let account = offline_account(name)?;
+        if !get_account_by_uuid(&account.profile.uuid).is_empty() {
+            return Err(anyhow!("This account has already been added"));
+        }
+        add_account(account, &TauriEventSink)
+    }
+    match add_offline_account(&name) {
+        Ok(x) => Ok(x),
+        Err(err) => {
+            error!("Failed to add offline account: {err}");
+            Err(())
+        }
+    }
+}
+
+#[tauri::command(async)]
+/// A command to add a microsoft account
+pub async fn add_microsoft_account(code: String) -> Result<(), AuthError> {
+    sign_in_microsoft(LoginPayload::AccessCode(code), &TauriEventSink)
+        .await
+        .map(|_| ())
+}
+
+/// Start the device code flow, the user enters the code on the returned url
+#[tauri::command(async)]
+pub async fn request_microsoft_device_code() -> Result<DeviceCode, AuthError> {
+    request_device_code().await.inspect_err(|err| {
+        error!("Failed to request a device code: {err}");
+    })
+}
+
+/// Wait until the user finishes signing in on the web page
+#[tauri::command(async)]
+pub async fn add_microsoft_account_by_device_code(
+    device_code: DeviceCode,
+) -> Result<(), AuthError> {
+    sign_in_microsoft(LoginPayload::DeviceCode(device_code), &TauriEventSink)
+        .await
+        .map(|_| ())
+}
+
+/// Sign in and add the account if it hasn't been added
+pub async fn sign_in_microsoft(
+    payload: LoginPayload,
+    sink: &dyn EventSink,
+) -> Result<Account, AuthError> {
+    info!("Signing in through Microsoft");
+    let account = microsoft_login(payload).await.inspect_err(|err| {
+        error!("Failed to sign in: {err}");
+    })?;
+    if !get_account_by_uuid(&account.profile.uuid).is_empty() {
+        error!("The account has already been added");
+        return Err(AuthError::AlreadyAdded);
+    }
+    add_account(account.clone(), sink).map_err(|err| AuthError::Storage(err.to_string()))?;
+    Ok(account)
+}
+
+#[tauri::command(async)]
+pub async fn refresh_microsoft_account_by_uuid(uuid: String) -> Result<Account, AuthError> {
+    refresh_microsoft_account(uuid, &TauriEventSink).await
+}
+
+pub async fn refresh_microsoft_account(
+    uuid: String,
+    sink: &dyn EventSink,
+) -> Result<Account, AuthError> {
+    let _refreshing = lock_refresh().await?;
+    refresh(find_microsoft_account(&uuid)?, sink).await
+}
+
+/// Make sure the access token works before launching the game
+pub async fn check_and_refresh(account: &Account, sink: &dyn EventSink) -> anyhow::Result<Account> {
+    info!("Checking account: {}", account.profile.uuid);
+    match account.account_type {
+        AccountType::Microsoft => {
+            Ok(check_and_refresh_microsoft(&account.profile.uuid, sink).await?)
+        }
+        AccountType::Yggdrasil { .. } => yggdrasil::check_and_refresh(account, sink).await,
+        AccountType::Offline => Ok(account.clone()),
+    }
+}
+
+async fn check_and_refresh_microsoft(
+    uuid: &str,
+    sink: &dyn EventSink,
+) -> Result<Account, AuthError> {
+    // Read the account again after the lock, it may have been refreshed in the meantime
+    let _refreshing = lock_refresh().await?;
+    let account = find_microsoft_account(uuid)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    if account
+        .token_deadline
+        .is_some_and(|deadline| deadline > now + EXPIRY_MARGIN)
+    {
+        match probe_profile(account.access_token.as_deref().unwrap_or_default()).await {
+            Ok(true) => {
+                info!("The access token is valid, no need to refresh");
+                return Ok(account);
+            }
+            Ok(false) => info!("The access token has been revoked"),
+            Err(err) => {
+                // The game can still be played offline
+                warn!("Failed to check the access token: {err}");
+                return Ok(account);
+            }
+        }
+    } else {
+        info!("The access token has expired or will expire soon");
+    }
+    refresh(account, sink).await
+}
+
+/// Refresh early, so the token doesn't expire while the game is starting
+const EXPIRY_MARGIN: u64 = 10 * 60;
+
+async fn lock_refresh() -> Result<service::RefreshGuard<'static>, AuthError> {
+    ACCOUNTS
+        .lock_refresh()
+        .await
+        .map_err(|err| AuthError::Storage(err.to_string()))
+}
+
+fn find_microsoft_account(uuid: &str) -> Result<Account, AuthError> {
+    ACCOUNTS
+        .find(uuid)
+        .map_err(|err| AuthError::Storage(err.to_string()))?
+        .filter(|account| account.account_type == AccountType::Microsoft)
+        .ok_or(AuthError::AccountNotFound)
+}
+
+/// Callers should hold [`service::AccountService::lock_refresh`]
+async fn refresh(account: Account, sink: &dyn EventSink) -> Result<Account, AuthError> {
+    let uuid = &account.profile.uuid;
+    info!("Start refreshing the account: {uuid}");
+    let refresh_token = account.refresh_token.ok_or(AuthError::InvalidGrant)?;
+    let refreshed = microsoft_login(LoginPayload::RefreshToken(refresh_token))
+        .await
+        .inspect_err(|err| error!("Failed to refresh the account {uuid}: {err}"))?;
+    update_account(refreshed.clone(), sink).map_err(|err| AuthError::Storage(err.to_string()))?;
+    Ok(refreshed)
+}
+
+#[cfg(not(debug_assertions))]
+#[tauri::command(async)]
+pub async fn refresh_all_microsoft_account() {
+    let accounts = get_accounts().unwrap_or_default();
+    for account in accounts {
+        if account.refresh_token.is_none() || account.account_type != AccountType::Microsoft {
+            continue;
+        }
+        // Errors are logged by `refresh`
+        let _ = refresh_microsoft_account(account.profile.uuid, &TauriEventSink).await;
+    }
+}
+
+#[cfg(debug_assertions)]
+#[tauri::command(async)]
+pub async fn refresh_all_microsoft_account() {
+    info!("Accounts are not refreshed on app launch in debug mode.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offline_uuid_matches_the_server() {
+        assert_eq!(
+            offline_uuid("Notch").to_string(),
+            "b50ad385-829d-3141-a216-7e7d7539ba7f"
+        );
+    }
+
+    #[test]
+    fn player_name_validation() {
+        assert!(is_valid_player_name("Steve_123"));
+        assert!(is_valid_player_name("abc"));
+        assert!(!is_valid_player_name("ab"));
+        assert!(!is_valid_player_name("a_name_that_is_too_long"));
+        assert!(!is_valid_player_name("bad name"));
+        assert!(!is_valid_player_name("玩家"));
+        assert!(offline_account("bad-name").is_err());
+    }
+}
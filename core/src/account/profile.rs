@@ -0,0 +1,332 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Change the skin, cape and name of Microsoft accounts through the Minecraft profile API
+
+use std::path::PathBuf;
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri_plugin_http::reqwest::{self, StatusCode};
+use uuid::Uuid;
+
+use super::{
+    get_account_by_uuid, is_valid_player_name,
+    microsoft::{parse_profile, ENDPOINTS},
+    update_account, Account, AccountType, ProfileError,
+};
+use crate::{event::TauriEventSink, HTTP_CLIENT};
+
+/// The same limit as the skin page of minecraft.net
+pub const MAX_SKIN_SIZE: usize = 24 * 1024;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SkinVariant {
+    Classic,
+    Slim,
+}
+
+impl SkinVariant {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Classic => "classic",
+            Self::Slim => "slim",
+        }
+    }
+}
+
+#[tauri::command(async)]
+pub async fn upload_skin(
+    uuid: String,
+    path: PathBuf,
+    variant: SkinVariant,
+) -> Result<Account, ProfileError> {
+    let skin = tokio::fs::read(&path)
+        .await
+        .map_err(|err| ProfileError::InvalidSkin(err.to_string()))?;
+    validate_skin(&skin)?;
+    let account = microsoft_account(&uuid)?;
+    info!("Uploading skin for {}", account.profile.profile_name);
+    let profile = ProfileApi::new(&account)
+        .upload_skin(&skin, variant)
+        .await?;
+    save_profile(account, profile).await
+}
+
+#[tauri::command(async)]
+pub async fn reset_skin(uuid: String) -> Result<Account, ProfileError> {
+    let account = microsoft_account(&uuid)?;
+    let profile = ProfileApi::new(&account).reset_skin().await?;
+    save_profile(account, profile).await
+}
+
+#[tauri::command(async)]
+pub async fn show_cape(uuid: String, cape_id: String) -> Result<Account, ProfileError> {
+    let account = microsoft_account(&uuid)?;
+    let profile = ProfileApi::new(&account).show_cape(&cape_id).await?;
+    save_profile(account, profile).await
+}
+
+#[tauri::command(async)]
+pub async fn hide_cape(uuid: String) -> Result<Account, ProfileError> {
+    let account = microsoft_account(&uuid)?;
+    let profile = ProfileApi::new(&account).hide_cape().await?;
+    save_profile(account, profile).await
+}
+
+/// Returns `true` if the name can be used
+#[tauri::command(async)]
+pub async fn check_profile_name(uuid: String, name: String) -> Result<bool, ProfileError> {
+    if !is_valid_player_name(&name) {
+        return Err(ProfileError::InvalidName);
+    }
+    let account = microsoft_account(&uuid)?;
+    let status = ProfileApi::new(&account).name_status(&name).await?;
+    Ok(status == "AVAILABLE")
+}
+
+#[tauri::command(async)]
+pub async fn change_profile_name(uuid: String, name: String) -> Result<Account, ProfileError> {
+    if !is_valid_player_name(&name) {
+        return Err(ProfileError::InvalidName);
+    }
+    let account = microsoft_account(&uuid)?;
+    let api = ProfileApi::new(&account);
+    let status = api.name_status(&name).await?;
+    if status != "AVAILABLE" {
+        return Err(ProfileError::NameUnavailable(status));
+    }
+    info!(
+        "Changing the name of {} to {name}",
+        account.profile.profile_name
+    );
+    let profile = api.change_name(&name).await?;
+    save_profile(account, profile).await
+}
+
+/// Check the file is a 64x64 PNG, or a 64x32 one in the legacy format
+pub fn validate_skin(skin: &[u8]) -> Result<(), ProfileError> {
+    if skin.len() > MAX_SKIN_SIZE {
+        return Err(ProfileError::InvalidSkin(format!(
+            "The file is larger than {} KiB",
+            MAX_SKIN_SIZE / 1024
+        )));
+    }
+    if skin.len() < 24 || skin[..8] != PNG_SIGNATURE || &skin[12..16] != b"IHDR" {
+        return Err(ProfileError::InvalidSkin("Not a PNG file".to_string()));
+    }
+    let width = u32::from_be_bytes(skin[16..20].try_into().unwrap());
+    let height = u32::from_be_bytes(skin[20..24].try_into().unwrap());
+    match (width, height) {
+        (64, 64) | (64, 32) => Ok(()),
+        _ => Err(ProfileError::InvalidSkin(format!(
+            "The size is {width}x{height}, it should be 64x64 or 64x32"
+        ))),
+    }
+}
+
+fn microsoft_account(uuid: &str) -> Result<Account, ProfileError> {
+    let account = get_account_by_uuid(uuid)
+        .into_iter()
+        .next()
+        .ok_or(ProfileError::AccountNotFound)?;
+    if account.account_type != AccountType::Microsoft {
+        return Err(ProfileError::NotMicrosoftAccount);
+    }
+    Ok(account)
+}
+
+async fn save_profile(mut account: Account, profile: Value) -> Result<Account, ProfileError> {
+    account.profile = parse_profile(&profile).await?;
+    update_account(account.clone(), &TauriEventSink)
+        .map_err(|err| ProfileError::Storage(err.to_string()))?;
+    Ok(account)
+}
+
+/// Requests return the updated profile, except [`ProfileApi::name_status`]
+struct ProfileApi<'a> {
+    base: &'a str,
+    access_token: &'a str,
+}
+
+impl<'a> ProfileApi<'a> {
+    fn new(account: &'a Account) -> Self {
+        Self {
+            base: &ENDPOINTS.minecraft,
+            access_token: account.access_token.as_deref().unwrap_or_default(),
+        }
+    }
+
+    async fn upload_skin(&self, skin: &[u8], variant: SkinVariant) -> Result<Value, ProfileError> {
+        let boundary = format!("ConicLauncher{}", Uuid::new_v4().simple());
+        let mut body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"variant\"\r\n\r\n{}\r\n",
+            variant.as_str()
+        )
+        .into_bytes();
+        body.extend(format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"skin.png\"\r\nContent-Type: image/png\r\n\r\n"
+        ).as_bytes());
+        body.extend(skin);
+        body.extend(format!("\r\n--{boundary}--\r\n").as_bytes());
+        self.send(
+            HTTP_CLIENT
+                .post(format!("{}/minecraft/profile/skins", self.base))
+                .header(
+                    "Content-Type",
+                    format!("multipart/form-data; boundary={boundary}"),
+                )
+                .body(body),
+        )
+        .await
+    }
+
+    async fn reset_skin(&self) -> Result<Value, ProfileError> {
+        self.send(HTTP_CLIENT.delete(format!("{}/minecraft/profile/skins/active", self.base)))
+            .await
+    }
+
+    async fn show_cape(&self, cape_id: &str) -> Result<Value, ProfileError> {
+        self.send(
+            HTTP_CLIENT
+                .put(format!("{}/minecraft/profile/capes/active", self.base))
+                .header("Content-Type", "application/json")
+                .body(json!({ "capeId": cape_id }).to_string()),
+        )
+        .await
+    }
+
+    async fn hide_cape(&self) -> Result<Value, ProfileError> {
+        self.send(HTTP_CLIENT.delete(format!("{}/minecraft/profile/capes/active", self.base)))
+            .await
+    }
+
+    /// `AVAILABLE`, `DUPLICATE` or `NOT_ALLOWED`
+    async fn name_status(&self, name: &str) -> Result<String, ProfileError> {
+        let response = self
+            .send(HTTP_CLIENT.get(format!(
+                "{}/minecraft/profile/name/{name}/available",
+                self.base
+            )))
+            .await?;
+        Ok(serde_json::from_value(response["status"].clone())?)
+    }
+
+    async fn change_name(&self, name: &str) -> Result<Value, ProfileError> {
+        self.send(HTTP_CLIENT.put(format!("{}/minecraft/profile/name/{name}", self.base)))
+            .await
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Value, ProfileError> {
+        let response = request
+            .header("Authorization", format!("Bearer {}", self.access_token))
+            .send()
+            .await?;
+        match response.status() {
+            StatusCode::TOO_MANY_REQUESTS => Err(ProfileError::RateLimited),
+            StatusCode::UNAUTHORIZED => Err(ProfileError::Unauthorized),
+            status if !status.is_success() => Err(ProfileError::InvalidResponse(format!(
+                "Minecraft services returned {status}"
+            ))),
+            _ => Ok(response.json().await?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::mock_server::{MockServer, Response};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut data = PNG_SIGNATURE.to_vec();
+        data.extend(13u32.to_be_bytes());
+        data.extend(b"IHDR");
+        data.extend(width.to_be_bytes());
+        data.extend(height.to_be_bytes());
+        data.extend([8, 6, 0, 0, 0]);
+        data
+    }
+
+    #[test]
+    fn skin_validation() {
+        assert!(validate_skin(&png(64, 64)).is_ok());
+        assert!(validate_skin(&png(64, 32)).is_ok());
+        assert!(matches!(
+            validate_skin(&png(128, 128)),
+            Err(ProfileError::InvalidSkin(_))
+        ));
+        assert!(validate_skin(b"GIF89a not a png file at all").is_err());
+        let mut large = png(64, 64);
+        large.resize(MAX_SKIN_SIZE + 1, 0);
+        assert!(validate_skin(&large).is_err());
+    }
+
+    fn profile(name: &str) -> Value {
+        json!({ "id": "uuid", "name": name, "skins": [], "capes": [] })
+    }
+
+    fn mock_profile_api() -> MockServer {
+        MockServer::start(|request| {
+            let authorized = request
+                .headers
+                .iter()
+                .any(|(name, value)| name == "authorization" && value == "Bearer mc-token");
+            if !authorized {
+                return Response::new(401, "");
+            }
+            match (request.method.as_str(), request.path.as_str()) {
+                ("POST", "/minecraft/profile/skins") => {
+                    assert!(request.body.contains("name=\"variant\"\r\n\r\nslim"));
+                    assert!(request.body.contains("filename=\"skin.png\""));
+                    Response::json(200, profile("Steve"))
+                }
+                ("PUT", "/minecraft/profile/capes/active") => {
+                    assert_eq!(request.json()["capeId"], "cape-id");
+                    Response::json(200, profile("Steve"))
+                }
+                ("GET", "/minecraft/profile/name/Alex/available") => {
+                    Response::json(200, json!({ "status": "AVAILABLE" }))
+                }
+                ("GET", "/minecraft/profile/name/Notch/available") => {
+                    Response::json(200, json!({ "status": "DUPLICATE" }))
+                }
+                ("PUT", "/minecraft/profile/name/Alex") => Response::json(200, profile("Alex")),
+                _ => Response::new(404, ""),
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn profile_requests() {
+        let server = mock_profile_api();
+        let api = ProfileApi {
+            base: &server.url,
+            access_token: "mc-token",
+        };
+        let response = api
+            .upload_skin(&png(64, 64), SkinVariant::Slim)
+            .await
+            .unwrap();
+        assert_eq!(response["name"], "Steve");
+        api.show_cape("cape-id").await.unwrap();
+        assert_eq!(api.name_status("Alex").await.unwrap(), "AVAILABLE");
+        assert_eq!(api.name_status("Notch").await.unwrap(), "DUPLICATE");
+        assert_eq!(api.change_name("Alex").await.unwrap()["name"], "Alex");
+    }
+
+    #[tokio::test]
+    async fn expired_token() {
+        let server = mock_profile_api();
+        let api = ProfileApi {
+            base: &server.url,
+            access_token: "expired",
+        };
+        assert_eq!(api.reset_skin().await, Err(ProfileError::Unauthorized));
+    }
+}
@@ -0,0 +1,276 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Accounts kept in memory, backed by `accounts.json`.
+//!
+//! The launcher and `conic-cli` may run at the same time, so every access to the
+//! file happens under an exclusive lock on `accounts.json.lock`, and changes are
+//! always applied to the latest content of the file. Token refreshes are serialized
+//! across processes by `accounts.refresh.lock`, a refresh token can only be used once.
+
+use std::{
+    fs::{File, OpenOptions},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use chacha20poly1305::Key;
+use futures::lock::{Mutex as AsyncMutex, MutexGuard};
+use once_cell::sync::Lazy;
+
+use super::{storage, Account};
+use crate::DATA_LOCATION;
+
+pub static ACCOUNTS: Lazy<AccountService> =
+    Lazy::new(|| AccountService::new(DATA_LOCATION.root.join("accounts.json"), None));
+
+pub struct AccountService {
+    path: PathBuf,
+    /// Uses the key of [`storage`] if not set
+    key: Option<Key>,
+    cache: Mutex<Option<Cache>>,
+    refreshing: AsyncMutex<()>,
+}
+
+/// Returned by [`AccountService::lock_refresh`], the file lock is released when dropped
+pub struct RefreshGuard<'a> {
+    _guard: MutexGuard<'a, ()>,
+    _file: File,
+}
+
+/// The accounts and the file they were read from
+struct Cache {
+    /// sha1 of the file, a rewrite may keep its length and modification time
+    sha1: Option<String>,
+    accounts: Vec<Account>,
+}
+
+impl AccountService {
+    pub fn new(path: PathBuf, key: Option<Key>) -> Self {
+        Self {
+            path,
+            key,
+            cache: Mutex::new(None),
+            refreshing: AsyncMutex::new(()),
+        }
+    }
+
+    /// All accounts, the tokens are only decrypted again if another process changed the file
+    pub fn list(&self) -> anyhow::Result<Vec<Account>> {
+        if let Some(accounts) = self.cached() {
+            return Ok(accounts);
+        }
+        let _lock = self.lock_file()?;
+        let accounts = self.read()?;
+        self.update_cache(accounts.clone());
+        Ok(accounts)
+    }
+
+    pub fn find(&self, uuid: &str) -> anyhow::Result<Option<Account>> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .find(|account| account.profile.uuid == uuid))
+    }
+
+    /// Read the file, change the accounts and write them back without releasing the lock.
+    ///
+    /// The file lock also serializes the changes in this process, the cache is only locked
+    /// to be replaced, so readers never wait for another process
+    pub fn modify<T>(
+        &self,
+        change: impl FnOnce(&mut Vec<Account>) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let _lock = self.lock_file()?;
+        let mut accounts = self.read()?;
+        let result = change(&mut accounts)?;
+        storage::write_accounts_to(&self.path, &accounts, self.key()?)?;
+        self.update_cache(accounts);
+        Ok(result)
+    }
+
+    /// Replace the account with the same uuid, returns `false` if it has been removed
+    pub fn replace(&self, account: Account) -> anyhow::Result<bool> {
+        self.modify(|accounts| {
+            match accounts
+                .iter_mut()
+                .find(|x| x.profile.uuid == account.profile.uuid)
+            {
+                Some(old) => {
+                    *old = account;
+                    Ok(true)
+                }
+                None => Ok(false),
+            }
+        })
+    }
+
+    /// Held while checking and refreshing tokens, so an account is not refreshed twice at the
+    /// same time, by this process or another one. Read the account again after locking.
+    pub async fn lock_refresh(&self) -> anyhow::Result<RefreshGuard<'_>> {
+        let guard = self.refreshing.lock().await;
+        let path = self.path.with_extension("refresh.lock");
+        let file = tokio::task::spawn_blocking(move || -> std::io::Result<File> {
+            let file = OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(path)?;
+            file.lock()?;
+            Ok(file)
+        })
+        .await??;
+        Ok(RefreshGuard {
+            _guard: guard,
+            _file: file,
+        })
+    }
+
+    fn key(&self) -> anyhow::Result<&Key> {
+        match &self.key {
+            Some(key) => Ok(key),
+            None => storage::key(),
+        }
+    }
+
+    fn read(&self) -> anyhow::Result<Vec<Account>> {
+        storage::read_accounts_from(&self.path, self.key()?)
+    }
+
+    /// Blocks until other processes release the lock, it is released when the file is dropped
+    fn lock_file(&self) -> anyhow::Result<File> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.path.with_extension("json.lock"))?;
+        file.lock()?;
+        Ok(file)
+    }
+
+    /// The file is replaced by renaming, so it can be read without the lock
+    fn file_sha1(&self) -> Option<String> {
+        std::fs::read(&self.path)
+            .ok()
+            .map(|data| sha1_smol::Sha1::from(data).digest().to_string())
+    }
+
+    /// Skips decrypting the tokens if the file is the one the cache was read from
+    fn cached(&self) -> Option<Vec<Account>> {
+        let sha1 = self.file_sha1();
+        let cache = self.cache.lock().unwrap();
+        cache
+            .as_ref()
+            .filter(|cache| cache.sha1 == sha1)
+            .map(|cache| cache.accounts.clone())
+    }
+
+    /// Called with the file locked, so the file belongs to the accounts
+    fn update_cache(&self, accounts: Vec<Account>) {
+        let sha1 = self.file_sha1();
+        *self.cache.lock().unwrap() = Some(Cache { sha1, accounts });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chacha20poly1305::{aead::OsRng, ChaCha20Poly1305, KeyInit};
+
+    use super::*;
+    use crate::{account::offline_account, utils::temp_dir::TempDir};
+
+    #[test]
+    fn concurrent_changes_are_kept() {
+        let dir = TempDir::new();
+        let path = dir.join("accounts.json");
+        let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        // Each service is like another process, with its own cache
+        let threads = (0..8)
+            .map(|i| {
+                let service = AccountService::new(path.clone(), Some(key));
+                std::thread::spawn(move || {
+                    let account = offline_account(&format!("Player{i}")).unwrap();
+                    service
+                        .modify(|accounts| {
+                            accounts.push(account);
+                            Ok(())
+                        })
+                        .unwrap();
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        let service = AccountService::new(path, Some(key));
+        assert_eq!(service.list().unwrap().len(), 8);
+    }
+
+    #[tokio::test]
+    async fn refreshes_wait_for_other_processes() {
+        let dir = TempDir::new();
+        let path = dir.join("accounts.json");
+        let launcher = AccountService::new(path.clone(), None);
+        let cli = AccountService::new(path, None);
+        let refreshing = launcher.lock_refresh().await.unwrap();
+        let waiting = tokio::time::timeout(Duration::from_millis(200), cli.lock_refresh()).await;
+        assert!(waiting.is_err());
+        drop(refreshing);
+        cli.lock_refresh().await.unwrap();
+    }
+
+    #[test]
+    fn rewrites_with_the_same_length_and_time_are_seen() {
+        let dir = TempDir::new();
+        let path = dir.join("accounts.json");
+        let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        let launcher = AccountService::new(path.clone(), Some(key));
+        let cli = AccountService::new(path.clone(), Some(key));
+        let mut account = offline_account("Steve").unwrap();
+        account.access_token = Some("aaaa".to_string());
+        let uuid = account.profile.uuid.clone();
+        cli.modify(|accounts| {
+            accounts.push(account.clone());
+            Ok(())
+        })
+        .unwrap();
+        assert!(launcher.find(&uuid).unwrap().is_some());
+        let metadata = std::fs::metadata(&path).unwrap();
+        account.access_token = Some("bbbb".to_string());
+        assert!(cli.replace(account).unwrap());
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), metadata.len());
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(metadata.modified().unwrap())
+            .unwrap();
+        let account = launcher.find(&uuid).unwrap().unwrap();
+        assert_eq!(account.access_token.as_deref(), Some("bbbb"));
+    }
+
+    #[test]
+    fn changes_of_other_processes_are_seen() {
+        let dir = TempDir::new();
+        let path = dir.join("accounts.json");
+        let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        let launcher = AccountService::new(path.clone(), Some(key));
+        let cli = AccountService::new(path, Some(key));
+        assert!(launcher.list().unwrap().is_empty());
+        let account = offline_account("Steve").unwrap();
+        let uuid = account.profile.uuid.clone();
+        cli.modify(|accounts| {
+            accounts.push(account);
+            Ok(())
+        })
+        .unwrap();
+        let mut account = launcher.find(&uuid).unwrap().unwrap();
+        account.access_token = Some("new".to_string());
+        assert!(launcher.replace(account).unwrap());
+        let account = cli.find(&uuid).unwrap().unwrap();
+        assert_eq!(account.access_token.as_deref(), Some("new"));
+    }
+}
@@ -0,0 +1,299 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Read and write `accounts.json` with the tokens encrypted.
+//!
+//! The key is kept in `accounts.key`, only readable by the current user. If
+//! `CONIC_ACCOUNTS_PASSPHRASE` is set, the key is derived from the passphrase instead.
+//! Files written by older versions have plaintext tokens, they are encrypted on first read.
+
+use std::{
+    io::{ErrorKind, Write},
+    path::Path,
+};
+
+use anyhow::anyhow;
+use argon2::Argon2;
+use base64::{engine::general_purpose, Engine};
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use log::info;
+use once_cell::sync::OnceCell;
+use uuid::Uuid;
+
+use super::{texture, Account};
+use crate::DATA_LOCATION;
+
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+const PASSPHRASE_ENV: &str = "CONIC_ACCOUNTS_PASSPHRASE";
+
+static KEY: OnceCell<Key> = OnceCell::new();
+
+pub(super) fn key() -> anyhow::Result<&'static Key> {
+    KEY.get_or_try_init(load_key)
+}
+
+fn load_key() -> anyhow::Result<Key> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        let salt = read_or_create_secret(&DATA_LOCATION.root.join("accounts.salt"), 16)?;
+        return derive_key(&passphrase, &salt);
+    }
+    let key = read_or_create_secret(&DATA_LOCATION.root.join("accounts.key"), 32)?;
+    Ok(*Key::from_slice(&key))
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> anyhow::Result<Key> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| anyhow!("Failed to derive the key: {err}"))?;
+    Ok(key)
+}
+
+/// Read `len` bytes from the file, or fill it with random bytes.
+///
+/// The launcher and `conic-cli` may create it at the same time, so it is written to a temp
+/// file and hardlinked into place, which fails if the other process has been faster
+fn read_or_create_secret(path: &Path, len: usize) -> anyhow::Result<Vec<u8>> {
+    match std::fs::read(path) {
+        Ok(secret) => return check_secret(path, secret, len),
+        Err(err) if err.kind() != ErrorKind::NotFound => return Err(err.into()),
+        Err(_) => {}
+    }
+    let mut secret = vec![0; len];
+    OsRng.fill_bytes(&mut secret);
+    let temp = path.with_extension(format!("{}.tmp", Uuid::new_v4()));
+    let result = write_secret(&temp, &secret).and_then(|_| std::fs::hard_link(&temp, path));
+    let _ = std::fs::remove_file(&temp);
+    match result {
+        Ok(_) => Ok(secret),
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+            check_secret(path, std::fs::read(path)?, len)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn write_secret(path: &Path, secret: &[u8]) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    file.write_all(secret)?;
+    file.sync_all()
+}
+
+fn check_secret(path: &Path, secret: Vec<u8>, len: usize) -> anyhow::Result<Vec<u8>> {
+    if secret.len() != len {
+        return Err(anyhow!("{} is broken", path.display()));
+    }
+    Ok(secret)
+}
+
+pub(super) fn read_accounts_from(path: &Path, key: &Key) -> anyhow::Result<Vec<Account>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let accounts: Vec<Account> = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    let mut has_plaintext = false;
+    let mut has_data_uri = false;
+    let accounts = accounts
+        .into_iter()
+        .map(|mut account| {
+            for value in token_fields(&mut account).into_iter().flatten() {
+                if value.starts_with(ENCRYPTED_PREFIX) {
+                    *value = decrypt(value, key)?;
+                } else {
+                    has_plaintext = true;
+                }
+            }
+            for skin in &mut account.profile.skins {
+                has_data_uri |= texture::migrate_data_uri(skin);
+            }
+            Ok(account)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if has_plaintext {
+        info!("Encrypting the tokens in {}", path.display());
+    }
+    if has_data_uri {
+        info!(
+            "Moving the skins in {} into the texture cache",
+            path.display()
+        );
+    }
+    if has_plaintext || has_data_uri {
+        write_accounts_to(path, &accounts, key)?;
+    }
+    Ok(accounts)
+}
+
+pub(super) fn write_accounts_to(
+    path: &Path,
+    accounts: &[Account],
+    key: &Key,
+) -> anyhow::Result<()> {
+    let accounts = accounts
+        .iter()
+        .cloned()
+        .map(|mut account| {
+            for value in token_fields(&mut account).into_iter().flatten() {
+                *value = encrypt(value, key)?;
+            }
+            Ok(account)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    write_atomically(path, serde_json::to_string_pretty(&accounts)?.as_bytes())
+}
+
+/// Write to a temp file in the same folder and rename it over the file, a crash while
+/// writing never leaves a truncated `accounts.json`
+fn write_atomically(path: &Path, data: &[u8]) -> anyhow::Result<()> {
+    fn write_and_rename(temp: &Path, path: &Path, data: &[u8]) -> std::io::Result<()> {
+        let mut file = std::fs::File::create(temp)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        std::fs::rename(temp, path)
+    }
+    let temp = path.with_extension(format!("json.{}.tmp", Uuid::new_v4()));
+    let result = write_and_rename(&temp, path, data);
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result?;
+    Ok(())
+}
+
+fn token_fields(account: &mut Account) -> [&mut Option<String>; 3] {
+    [
+        &mut account.refresh_token,
+        &mut account.access_token,
+        &mut account.client_token,
+    ]
+}
+
+fn encrypt(plaintext: &str, key: &Key) -> anyhow::Result<String> {
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = ChaCha20Poly1305::new(key)
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|_| anyhow!("Failed to encrypt the token"))?;
+    let mut data = nonce.to_vec();
+    data.extend(ciphertext);
+    Ok(ENCRYPTED_PREFIX.to_string() + &general_purpose::STANDARD.encode(data))
+}
+
+fn decrypt(value: &str, key: &Key) -> anyhow::Result<String> {
+    let data = general_purpose::STANDARD.decode(&value[ENCRYPTED_PREFIX.len()..])?;
+    if data.len() < 12 {
+        return Err(anyhow!("The encrypted token is too short"));
+    }
+    let (nonce, ciphertext) = data.split_at(12);
+    let plaintext = ChaCha20Poly1305::new(key)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("Failed to decrypt the token, the key may be wrong"))?;
+    Ok(String::from_utf8(plaintext)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        account::{AccountType, Profile},
+        utils::temp_dir::TempDir,
+    };
+
+    fn account() -> Account {
+        Account {
+            refresh_token: Some("refresh".to_string()),
+            access_token: Some("access".to_string()),
+            token_deadline: Some(0),
+            client_token: None,
+            profile: Profile {
+                profile_name: "Steve".to_string(),
+                uuid: "uuid".to_string(),
+                skins: vec![],
+                capes: vec![],
+            },
+            account_type: AccountType::Microsoft,
+        }
+    }
+
+    #[test]
+    fn tokens_are_encrypted() {
+        let dir = TempDir::new();
+        let path = dir.join("accounts.json");
+        let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        write_accounts_to(&path, &[account()], &key).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("\"refresh\""));
+        assert!(!contents.contains("\"access\""));
+        assert!(contents.contains("Steve"));
+        let accounts = read_accounts_from(&path, &key).unwrap();
+        assert_eq!(accounts[0].refresh_token.as_deref(), Some("refresh"));
+        assert_eq!(accounts[0].access_token.as_deref(), Some("access"));
+        assert_eq!(accounts[0].client_token, None);
+        let wrong_key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        assert!(read_accounts_from(&path, &wrong_key).is_err());
+    }
+
+    #[test]
+    fn plaintext_file_is_migrated() {
+        let dir = TempDir::new();
+        let path = dir.join("accounts.json");
+        std::fs::write(&path, serde_json::to_string(&[account()]).unwrap()).unwrap();
+        let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        let accounts = read_accounts_from(&path, &key).unwrap();
+        assert_eq!(accounts[0].refresh_token.as_deref(), Some("refresh"));
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("\"refresh\""));
+        assert!(contents.contains(ENCRYPTED_PREFIX));
+        let files: Vec<_> = std::fs::read_dir(&*dir).unwrap().collect();
+        assert_eq!(files.len(), 1, "no temp file is left");
+    }
+
+    #[test]
+    fn key_file_is_private() {
+        let dir = TempDir::new();
+        let path = dir.join("accounts.key");
+        let key = read_or_create_secret(&path, 32).unwrap();
+        assert_eq!(read_or_create_secret(&path, 32).unwrap(), key);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn key_file_is_created_once() {
+        let dir = TempDir::new();
+        let path = dir.join("accounts.key");
+        let threads = (0..8)
+            .map(|_| {
+                let path = path.clone();
+                std::thread::spawn(move || read_or_create_secret(&path, 32).unwrap())
+            })
+            .collect::<Vec<_>>();
+        let keys: Vec<_> = threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .collect();
+        assert!(keys.iter().all(|key| *key == keys[0]));
+        assert_eq!(std::fs::read(&path).unwrap(), keys[0]);
+        let files: Vec<_> = std::fs::read_dir(&*dir).unwrap().collect();
+        assert_eq!(files.len(), 1, "no temp file is left");
+    }
+
+    #[test]
+    fn passphrase_derived_key() {
+        let salt = [7; 16];
+        let key = derive_key("correct horse", &salt).unwrap();
+        assert_eq!(key, derive_key("correct horse", &salt).unwrap());
+        assert_ne!(key, derive_key("battery staple", &salt).unwrap());
+    }
+}
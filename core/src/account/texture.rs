@@ -0,0 +1,339 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Skin and cape textures cached on disk, named by the texture hash
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail};
+use base64::{engine::general_purpose, Engine};
+use log::{error, warn};
+use uuid::Uuid;
+
+use super::{get_account_by_uuid, Skin};
+use crate::{DATA_LOCATION, HTTP_CLIENT};
+
+/// Head image of the active skin as a data URI, for the account list
+#[tauri::command(async)]
+pub async fn get_account_avatar(uuid: String, size: u32) -> Result<String, ()> {
+    async fn get_account_avatar(uuid: &str, size: u32) -> anyhow::Result<String> {
+        let account = get_account_by_uuid(uuid)
+            .into_iter()
+            .next()
+            .ok_or(anyhow!("The account has not been added"))?;
+        let skin = account
+            .profile
+            .skins
+            .iter()
+            .find(|skin| skin.state == "ACTIVE")
+            .or(account.profile.skins.first())
+            .ok_or(anyhow!("The account doesn't have a skin"))?;
+        let head = load_head(skin, size.clamp(8, 512)).await?;
+        Ok(format!(
+            "data:image/png;base64,{}",
+            general_purpose::STANDARD.encode(head)
+        ))
+    }
+    get_account_avatar(&uuid, size).await.map_err(|err| {
+        error!("Failed to get the avatar of {uuid}: {err}");
+    })
+}
+
+const TEXTURES_URL: &str = "http://textures.minecraft.net/texture";
+const DATA_URI_PREFIX: &str = "data:image/png;base64,";
+
+/// The url saved with the account, the texture itself is only kept in the cache
+pub fn texture_url(key: &str) -> String {
+    format!("{TEXTURES_URL}/{key}")
+}
+
+/// The hash at the end of a texture url, like `http://textures.minecraft.net/texture/<hash>`
+pub fn texture_key(url: &str) -> Option<&str> {
+    url.rsplit('/').next().filter(|key| is_valid_key(key))
+}
+
+fn is_valid_key(key: &str) -> bool {
+    key.len() >= 2 && key.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+fn texture_path(key: &str) -> anyhow::Result<PathBuf> {
+    texture_path_in(&DATA_LOCATION.cache.join("textures"), key)
+}
+
+fn texture_path_in(textures: &Path, key: &str) -> anyhow::Result<PathBuf> {
+    if !is_valid_key(key) {
+        bail!("Invalid texture key: {key}");
+    }
+    Ok(textures.join(&key[..2]).join(key))
+}
+
+/// Older versions saved the skins in accounts.json as data URIs, move them into the cache.
+///
+/// Returns whether the skin has been changed
+pub(super) fn migrate_data_uri(skin: &mut Skin) -> bool {
+    migrate_data_uri_in(&DATA_LOCATION.cache.join("textures"), skin)
+}
+
+fn migrate_data_uri_in(textures: &Path, skin: &mut Skin) -> bool {
+    let Some(encoded) = skin.url.strip_prefix(DATA_URI_PREFIX) else {
+        return false;
+    };
+    let Ok(path) = texture_path_in(textures, &skin.texture_key) else {
+        return false;
+    };
+    if !path.exists() {
+        // The texture is downloaded again when it's needed if this fails
+        let saved = general_purpose::STANDARD_NO_PAD
+            .decode(encoded.trim_end_matches('='))
+            .map_err(anyhow::Error::from)
+            .and_then(|texture| save(&path, &texture));
+        if let Err(err) = saved {
+            warn!(
+                "Failed to move the skin {} into the cache: {err}",
+                skin.texture_key
+            );
+        }
+    }
+    skin.url = texture_url(&skin.texture_key);
+    true
+}
+
+/// Read the texture from the cache, or download it
+pub async fn load_texture(url: &str, key: &str) -> anyhow::Result<Vec<u8>> {
+    let path = texture_path(key)?;
+    if let Ok(texture) = tokio::fs::read(&path).await {
+        return Ok(texture);
+    }
+    let texture = HTTP_CLIENT
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?
+        .to_vec();
+    save(&path, &texture)?;
+    Ok(texture)
+}
+
+async fn load_head(skin: &Skin, size: u32) -> anyhow::Result<Vec<u8>> {
+    let key = if is_valid_key(&skin.texture_key) {
+        skin.texture_key.as_str()
+    } else {
+        texture_key(&skin.url).ok_or(anyhow!("No texture key"))?
+    };
+    let path = texture_path(key)?.with_file_name(format!("{key}-head-{size}.png"));
+    if let Ok(head) = tokio::fs::read(&path).await {
+        return Ok(head);
+    }
+    let head = render_head(&load_texture(&skin.url, key).await?, size)?;
+    save(&path, &head)?;
+    Ok(head)
+}
+
+/// The same texture may be saved by several requests at the same time, each one writes its
+/// own temp file
+fn save(path: &Path, data: &[u8]) -> anyhow::Result<()> {
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    let temp = path.with_extension(format!("{}.part", Uuid::new_v4()));
+    let result = std::fs::write(&temp, data).and_then(|_| std::fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result?;
+    Ok(())
+}
+
+struct Image {
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+impl Image {
+    fn pixel(&self, x: u32, y: u32) -> &[u8] {
+        let index = ((y * self.width + x) * 4) as usize;
+        &self.rgba[index..index + 4]
+    }
+}
+
+fn decode_png(data: &[u8]) -> anyhow::Result<Image> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    buffer.truncate(info.buffer_size());
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer
+            .chunks(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => buffer
+            .chunks(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Grayscale => buffer.iter().flat_map(|&p| [p, p, p, 255]).collect(),
+        png::ColorType::Indexed => bail!("The palette is not expanded"),
+    };
+    Ok(Image {
+        width: info.width,
+        height: info.height,
+        rgba,
+    })
+}
+
+fn encode_png(width: u32, height: u32, rgba: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut data, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(rgba)?;
+    Ok(data)
+}
+
+/// Render the face with the hat layer on it, the face is a little smaller than the hat like HMCL
+pub fn render_head(skin: &[u8], size: u32) -> anyhow::Result<Vec<u8>> {
+    let skin = decode_png(skin)?;
+    let scale = skin.width / 64;
+    if scale == 0 || skin.height < 16 * scale {
+        bail!("Invalid skin size {}x{}", skin.width, skin.height);
+    }
+    let mut head = vec![0; (size * size * 4) as usize];
+    let offset = (size as f64 / 18.0).round() as u32;
+    draw_square(&skin, scale, (8, 8), &mut head, size, offset);
+    draw_square(&skin, scale, (40, 8), &mut head, size, 0);
+    encode_png(size, size, &head)
+}
+
+/// Draw the 8x8 square at `from` of the skin, with nearest neighbor scaling
+fn draw_square(
+    skin: &Image,
+    scale: u32,
+    from: (u32, u32),
+    head: &mut [u8],
+    size: u32,
+    offset: u32,
+) {
+    let length = size - offset * 2;
+    for y in 0..length {
+        for x in 0..length {
+            let source = skin.pixel(
+                from.0 * scale + x * 8 * scale / length,
+                from.1 * scale + y * 8 * scale / length,
+            );
+            let index = (((y + offset) * size + x + offset) * 4) as usize;
+            blend(&mut head[index..index + 4], source);
+        }
+    }
+}
+
+/// Source over alpha compositing
+fn blend(target: &mut [u8], source: &[u8]) {
+    let source_alpha = source[3] as f32 / 255.0;
+    let target_alpha = target[3] as f32 / 255.0;
+    let alpha = source_alpha + target_alpha * (1.0 - source_alpha);
+    if alpha == 0.0 {
+        return;
+    }
+    for i in 0..3 {
+        let color = (source[i] as f32 * source_alpha
+            + target[i] as f32 * target_alpha * (1.0 - source_alpha))
+            / alpha;
+        target[i] = color.round() as u8;
+    }
+    target[3] = (alpha * 255.0).round() as u8;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::temp_dir::TempDir;
+
+    fn skin() -> Vec<u8> {
+        let mut rgba = vec![0; 64 * 64 * 4];
+        let mut fill = |x0: usize, y0: usize, color: [u8; 4]| {
+            for y in y0..y0 + 8 {
+                for x in x0..x0 + 8 {
+                    let index = (y * 64 + x) * 4;
+                    rgba[index..index + 4].copy_from_slice(&color);
+                }
+            }
+        };
+        // Red face, the hat layer is transparent except its top left pixel
+        fill(8, 8, [255, 0, 0, 255]);
+        fill(40, 8, [0, 0, 0, 0]);
+        rgba[(8 * 64 + 40) * 4..(8 * 64 + 40) * 4 + 4].copy_from_slice(&[0, 0, 255, 255]);
+        encode_png(64, 64, &rgba).unwrap()
+    }
+
+    #[test]
+    fn render_head_with_hat() {
+        let head = decode_png(&render_head(&skin(), 36).unwrap()).unwrap();
+        assert_eq!((head.width, head.height), (36, 36));
+        // The hat pixel covers the top left corner
+        assert_eq!(head.pixel(0, 0), [0, 0, 255, 255]);
+        // The face is 2 pixels smaller than the hat on each side
+        assert_eq!(head.pixel(35, 35), [0, 0, 0, 0]);
+        assert_eq!(head.pixel(33, 33), [255, 0, 0, 255]);
+        assert_eq!(head.pixel(18, 18), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn invalid_skin() {
+        assert!(render_head(b"not a png", 32).is_err());
+        let small = encode_png(32, 8, &[0; 32 * 8 * 4]).unwrap();
+        assert!(render_head(&small, 32).is_err());
+    }
+
+    #[test]
+    fn key_from_url() {
+        assert_eq!(
+            texture_key("http://textures.minecraft.net/texture/1a2b3c"),
+            Some("1a2b3c")
+        );
+        assert_eq!(texture_key("data:image/png;base64,AAAA/../x"), None);
+        assert!(texture_path("../etc").is_err());
+    }
+
+    #[test]
+    fn concurrent_saves_of_one_texture() {
+        let dir = TempDir::new();
+        let path = dir.join("ab").join("key-head-64.png");
+        let threads = (0..8)
+            .map(|_| {
+                let path = path.clone();
+                std::thread::spawn(move || save(&path, b"head").unwrap())
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(std::fs::read(&path).unwrap(), b"head");
+        let files: Vec<_> = std::fs::read_dir(dir.join("ab")).unwrap().collect();
+        assert_eq!(files.len(), 1, "no temp file is left");
+    }
+
+    #[test]
+    fn data_uri_is_moved_into_the_cache() {
+        let textures = TempDir::new();
+        let key = "1a2b3c";
+        let mut saved = Skin {
+            id: "id".to_string(),
+            state: "ACTIVE".to_string(),
+            texture_key: key.to_string(),
+            url: format!(
+                "{DATA_URI_PREFIX}{}",
+                general_purpose::STANDARD_NO_PAD.encode(skin())
+            ),
+            variant: "CLASSIC".to_string(),
+        };
+        assert!(migrate_data_uri_in(&textures, &mut saved));
+        assert_eq!(saved.url, texture_url(key));
+        let path = texture_path_in(&textures, key).unwrap();
+        assert_eq!(std::fs::read(path).unwrap(), skin());
+        assert!(!migrate_data_uri_in(&textures, &mut saved));
+    }
+}
@@ -0,0 +1,527 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Third-party authentication servers implementing the Yggdrasil API.
+//!
+//! The game talks to them through authlib-injector <https://github.com/yushijinhun/authlib-injector>
+
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use base64::{engine::general_purpose, Engine};
+use log::{error, info, warn};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tauri_plugin_http::reqwest::{self, StatusCode};
+use uuid::Uuid;
+
+use super::{
+    add_account, get_account_by_uuid, update_account, Account, AccountType, AuthError, Profile,
+    ACCOUNTS,
+};
+use crate::{
+    event::{EventSink, TauriEventSink},
+    DATA_LOCATION, HTTP_CLIENT,
+};
+
+const AUTHLIB_INJECTOR_LATEST: &str = "https://authlib-injector.yushi.moe/artifact/latest.json";
+
+#[derive(Clone)]
+pub struct YggdrasilAgent {
+    pub jar: PathBuf,
+    /// API root of the authentication server
+    pub server: String,
+    /// Base64 encoded server metadata, saves a request when the game starts
+    pub prefetched: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct YggdrasilProfile {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AuthResponse {
+    access_token: String,
+    client_token: String,
+    #[serde(default)]
+    available_profiles: Vec<YggdrasilProfile>,
+    selected_profile: Option<YggdrasilProfile>,
+}
+
+#[derive(Deserialize)]
+struct AuthlibInjectorArtifact {
+    version: String,
+    download_url: String,
+    checksums: AuthlibInjectorChecksums,
+}
+
+#[derive(Deserialize)]
+struct AuthlibInjectorChecksums {
+    sha256: String,
+}
+
+#[tauri::command(async)]
+pub async fn add_yggdrasil_account(
+    server: String,
+    username: String,
+    password: String,
+) -> Result<(), AuthError> {
+    async fn add_yggdrasil_account(
+        server: &str,
+        username: &str,
+        password: &str,
+    ) -> Result<(), AuthError> {
+        info!("Signing in through {server}");
+        let server = resolve_api_root(server).await?;
+        let account = authenticate(&server, username, password).await?;
+        if !get_account_by_uuid(&account.profile.uuid).is_empty() {
+            return Err(AuthError::AlreadyAdded);
+        }
+        add_account(account, &TauriEventSink).map_err(|err| AuthError::Storage(err.to_string()))
+    }
+    add_yggdrasil_account(&server, &username, &password)
+        .await
+        .inspect_err(|err| error!("Failed to add Yggdrasil account: {err}"))
+}
+
+/// Follow the API Location Indication, so users can enter the homepage of the skin site
+pub async fn resolve_api_root(url: &str) -> Result<String, AuthError> {
+    let url = if url.starts_with("http://") || url.starts_with("https://") {
+        url.to_string()
+    } else {
+        format!("https://{url}")
+    };
+    let response = HTTP_CLIENT.get(&url).send().await?;
+    let root = match response.headers().get("x-authlib-injector-api-location") {
+        Some(location) => location
+            .to_str()
+            .ok()
+            .and_then(|location| reqwest::Url::parse(&url).ok()?.join(location).ok())
+            .ok_or_else(|| {
+                AuthError::InvalidResponse(format!("Invalid API location: {location:?}"))
+            })?
+            .to_string(),
+        None => url,
+    };
+    Ok(root.trim_end_matches('/').to_string())
+}
+
+pub async fn authenticate(
+    server: &str,
+    username: &str,
+    password: &str,
+) -> Result<Account, AuthError> {
+    let client_token = Uuid::new_v4().simple().to_string();
+    let body = json!({
+        "agent": { "name": "Minecraft", "version": 1 },
+        "username": username,
+        "password": password,
+        "clientToken": client_token,
+        "requestUser": false,
+    });
+    let response: AuthResponse = post(server, "authserver/authenticate", body).await?;
+    let response = match (
+        &response.selected_profile,
+        response.available_profiles.first(),
+    ) {
+        (Some(_), _) => response,
+        // The server doesn't select a profile when the user has more than one
+        (None, Some(profile)) => {
+            refresh_token(
+                server,
+                &response.access_token,
+                &response.client_token,
+                Some(profile),
+            )
+            .await?
+        }
+        (None, None) => return Err(AuthError::NoProfile),
+    };
+    into_account(server, response)
+}
+
+pub async fn refresh(account: &Account) -> anyhow::Result<Account> {
+    let AccountType::Yggdrasil { server } = &account.account_type else {
+        return Err(anyhow!("Not a Yggdrasil account"));
+    };
+    let profile = YggdrasilProfile {
+        id: account.profile.uuid.clone(),
+        name: account.profile.profile_name.clone(),
+    };
+    let response = refresh_token(
+        server,
+        account.access_token.as_deref().unwrap_or_default(),
+        account.client_token.as_deref().unwrap_or_default(),
+        Some(&profile),
+    )
+    .await?;
+    into_account(server, response)
+}
+
+pub async fn validate(account: &Account) -> anyhow::Result<bool> {
+    let AccountType::Yggdrasil { server } = &account.account_type else {
+        return Err(anyhow!("Not a Yggdrasil account"));
+    };
+    let body = json!({
+        "accessToken": account.access_token,
+        "clientToken": account.client_token,
+    });
+    let response = HTTP_CLIENT
+        .post(format!("{server}/authserver/validate"))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+    Ok(response.status().is_success())
+}
+
+/// Refresh the access token if the server doesn't accept it anymore
+pub async fn check_and_refresh(account: &Account, sink: &dyn EventSink) -> anyhow::Result<Account> {
+    // Read the account again after the lock, it may have been refreshed in the meantime
+    let _refreshing = ACCOUNTS.lock_refresh().await?;
+    let account = &ACCOUNTS
+        .find(&account.profile.uuid)?
+        .ok_or(anyhow!("The account has not been added"))?;
+    match validate(account).await {
+        Ok(true) => {
+            info!("The access token is still valid");
+            return Ok(account.clone());
+        }
+        Ok(false) => info!("The access token is invalid, refreshing"),
+        Err(err) => {
+            // The game can still be played offline
+            warn!("Failed to check the access token: {err}");
+            return Ok(account.clone());
+        }
+    }
+    let refreshed_account = refresh(account).await?;
+    update_account(refreshed_account.clone(), sink)?;
+    Ok(refreshed_account)
+}
+
+async fn refresh_token(
+    server: &str,
+    access_token: &str,
+    client_token: &str,
+    profile: Option<&YggdrasilProfile>,
+) -> Result<AuthResponse, AuthError> {
+    let mut body = json!({
+        "accessToken": access_token,
+        "clientToken": client_token,
+        "requestUser": false,
+    });
+    if let Some(profile) = profile {
+        body["selectedProfile"] = serde_json::to_value(profile)?;
+    }
+    post(server, "authserver/refresh", body).await
+}
+
+/// The `errorMessage` of a failed request is shown to the user, e.g. a wrong password
+async fn post<T: DeserializeOwned>(
+    server: &str,
+    endpoint: &str,
+    body: Value,
+) -> Result<T, AuthError> {
+    let response = HTTP_CLIENT
+        .post(format!("{server}/{endpoint}"))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let error: Value = response.json().await.unwrap_or_default();
+        return Err(match error["errorMessage"].as_str() {
+            Some(message) => AuthError::Rejected(message.to_string()),
+            None if status == StatusCode::TOO_MANY_REQUESTS => AuthError::RateLimited,
+            None => AuthError::InvalidResponse(status.to_string()),
+        });
+    }
+    Ok(response.json().await?)
+}
+
+fn into_account(server: &str, response: AuthResponse) -> Result<Account, AuthError> {
+    let profile = response.selected_profile.ok_or(AuthError::InvalidResponse(
+        "No selected profile".to_string(),
+    ))?;
+    Ok(Account {
+        refresh_token: None,
+        access_token: Some(response.access_token),
+        token_deadline: None,
+        client_token: Some(response.client_token),
+        profile: Profile {
+            profile_name: profile.name,
+            uuid: profile.id,
+            skins: vec![],
+            capes: vec![],
+        },
+        account_type: AccountType::Yggdrasil {
+            server: server.to_string(),
+        },
+    })
+}
+
+/// Download the server metadata for `-Dauthlibinjector.yggdrasil.prefetched`
+pub async fn fetch_metadata(server: &str) -> anyhow::Result<String> {
+    let metadata = HTTP_CLIENT
+        .get(server)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    Ok(general_purpose::STANDARD.encode(metadata))
+}
+
+/// Download authlib-injector if it hasn't been downloaded
+pub async fn ensure_authlib_injector() -> anyhow::Result<PathBuf> {
+    install_authlib_injector(&DATA_LOCATION.runtimes, AUTHLIB_INJECTOR_LATEST).await
+}
+
+/// The jar is loaded into the game as a java agent, so it is checked against the sha256
+/// published in `latest.json`, and the cached one is checked against the saved sha256
+async fn install_authlib_injector(dir: &Path, latest_url: &str) -> anyhow::Result<PathBuf> {
+    let jar = dir.join("authlib-injector.jar");
+    let checksum = dir.join("authlib-injector.jar.sha256");
+    if jar.exists() {
+        let expected = tokio::fs::read_to_string(&checksum)
+            .await
+            .unwrap_or_default();
+        if sha256_hex(&tokio::fs::read(&jar).await?) == expected.trim() {
+            return Ok(jar);
+        }
+        warn!("The cached authlib-injector doesn't match its sha256, downloading it again");
+    }
+    let artifact: AuthlibInjectorArtifact = HTTP_CLIENT
+        .get(latest_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    info!("Downloading authlib-injector {}", artifact.version);
+    let content = HTTP_CLIENT
+        .get(&artifact.download_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    let sha256 = sha256_hex(&content);
+    if !sha256.eq_ignore_ascii_case(artifact.checksums.sha256.trim()) {
+        return Err(anyhow!(
+            "The sha256 of authlib-injector {} mismatches, expected {}, got {sha256}",
+            artifact.version,
+            artifact.checksums.sha256
+        ));
+    }
+    tokio::fs::create_dir_all(dir).await?;
+    let temp = jar.with_extension("jar.part");
+    tokio::fs::write(&temp, content).await?;
+    tokio::fs::rename(&temp, &jar).await?;
+    tokio::fs::write(&checksum, sha256).await?;
+    Ok(jar)
+}
+
+fn sha256_hex(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+pub async fn prepare_agent(server: &str) -> anyhow::Result<YggdrasilAgent> {
+    let jar = ensure_authlib_injector().await?;
+    let prefetched = match fetch_metadata(server).await {
+        Ok(x) => Some(x),
+        Err(err) => {
+            warn!("Failed to prefetch the metadata of {server}: {err}");
+            None
+        }
+    };
+    Ok(YggdrasilAgent {
+        jar,
+        server: server.to_string(),
+        prefetched,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{
+        mock_server::{MockServer, Response},
+        temp_dir::TempDir,
+    };
+
+    fn profile(id: &str, name: &str) -> Value {
+        json!({ "id": id, "name": name })
+    }
+
+    fn mock_yggdrasil() -> MockServer {
+        MockServer::start(|request| match request.path.as_str() {
+            "/authserver/authenticate" => {
+                let body = request.json();
+                assert_eq!(body["agent"]["name"], "Minecraft");
+                if body["password"] != "secret" {
+                    return Response::json(
+                        403,
+                        json!({
+                            "error": "ForbiddenOperationException",
+                            "errorMessage": "Invalid credentials. Invalid username or password."
+                        }),
+                    );
+                }
+                let mut response = json!({
+                    "accessToken": "token-1",
+                    "clientToken": body["clientToken"],
+                    "availableProfiles": [profile("uuid-a", "Alex"), profile("uuid-s", "Steve")],
+                });
+                if body["username"] == "alex@example.com" {
+                    response["selectedProfile"] = profile("uuid-a", "Alex");
+                }
+                Response::json(200, response)
+            }
+            "/authserver/refresh" => {
+                let body = request.json();
+                Response::json(
+                    200,
+                    json!({
+                        "accessToken": "token-2",
+                        "clientToken": body["clientToken"],
+                        "selectedProfile": body["selectedProfile"],
+                    }),
+                )
+            }
+            "/authserver/validate" => {
+                if request.json()["accessToken"] == "token-2" {
+                    Response::new(204, "")
+                } else {
+                    Response::json(403, json!({ "error": "ForbiddenOperationException" }))
+                }
+            }
+            _ => Response::new(404, ""),
+        })
+    }
+
+    #[tokio::test]
+    async fn authenticate_with_selected_profile() {
+        let server = mock_yggdrasil();
+        let account = authenticate(&server.url, "alex@example.com", "secret")
+            .await
+            .unwrap();
+        assert_eq!(account.profile.uuid, "uuid-a");
+        assert_eq!(account.profile.profile_name, "Alex");
+        assert_eq!(account.access_token.as_deref(), Some("token-1"));
+        assert!(account.client_token.is_some());
+        assert!(
+            account.account_type
+                == AccountType::Yggdrasil {
+                    server: server.url.clone()
+                }
+        );
+        assert!(!validate(&account).await.unwrap());
+        let account = refresh(&account).await.unwrap();
+        assert_eq!(account.access_token.as_deref(), Some("token-2"));
+        assert_eq!(account.profile.uuid, "uuid-a");
+        assert!(validate(&account).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn authenticate_binds_the_first_profile() {
+        let server = mock_yggdrasil();
+        let account = authenticate(&server.url, "both@example.com", "secret")
+            .await
+            .unwrap();
+        assert_eq!(account.profile.profile_name, "Alex");
+        assert_eq!(account.access_token.as_deref(), Some("token-2"));
+        let paths: Vec<_> = server.requests().into_iter().map(|x| x.1).collect();
+        assert_eq!(paths, ["/authserver/authenticate", "/authserver/refresh"]);
+    }
+
+    #[tokio::test]
+    async fn authenticate_with_wrong_password() {
+        let server = mock_yggdrasil();
+        let error = authenticate(&server.url, "alex@example.com", "wrong")
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            error,
+            AuthError::Rejected("Invalid credentials. Invalid username or password.".to_string())
+        );
+        let error = authenticate(
+            &format!("{}/wrong", server.url),
+            "alex@example.com",
+            "secret",
+        )
+        .await
+        .err()
+        .unwrap();
+        assert!(matches!(error, AuthError::InvalidResponse(_)));
+    }
+
+    fn mock_authlib_injector(jar: &'static str, sha256: String) -> MockServer {
+        MockServer::start(move |request| {
+            let host = request.header("host").unwrap_or_default();
+            match request.path.as_str() {
+                "/latest.json" => Response::json(
+                    200,
+                    json!({
+                        "version": "1.2.5",
+                        "download_url": format!("http://{host}/authlib-injector.jar"),
+                        "checksums": { "sha256": sha256 },
+                    }),
+                ),
+                "/authlib-injector.jar" => Response::new(200, jar),
+                _ => Response::new(404, ""),
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn authlib_injector_is_checked() {
+        let server = mock_authlib_injector("agent", sha256_hex(b"agent"));
+        let dir = TempDir::new();
+        let latest = format!("{}/latest.json", server.url);
+        let jar = install_authlib_injector(&dir, &latest).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&jar).unwrap(), "agent");
+        install_authlib_injector(&dir, &latest).await.unwrap();
+        assert_eq!(server.requests().len(), 2);
+
+        std::fs::write(&jar, "tampered").unwrap();
+        let jar = install_authlib_injector(&dir, &latest).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&jar).unwrap(), "agent");
+        assert_eq!(server.requests().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn authlib_injector_with_wrong_sha256_is_not_kept() {
+        let server = mock_authlib_injector("agent", sha256_hex(b"other"));
+        let dir = TempDir::new();
+        let latest = format!("{}/latest.json", server.url);
+        assert!(install_authlib_injector(&dir, &latest).await.is_err());
+        assert!(!dir.join("authlib-injector.jar").exists());
+    }
+
+    #[tokio::test]
+    async fn api_location_indication_and_metadata() {
+        let metadata = r#"{"meta":{"serverName":"Test"},"skinDomains":[]}"#;
+        let server = MockServer::start(move |request| match request.path.as_str() {
+            "/" => Response::new(200, "<html></html>")
+                .header("X-Authlib-Injector-API-Location", "/api/yggdrasil/"),
+            "/api/yggdrasil" => Response::json(200, serde_json::from_str(metadata).unwrap()),
+            _ => Response::new(404, ""),
+        });
+        let root = resolve_api_root(&format!("{}/", server.url)).await.unwrap();
+        assert_eq!(root, format!("{}/api/yggdrasil", server.url));
+        let prefetched = fetch_metadata(&root).await.unwrap();
+        let decoded = general_purpose::STANDARD.decode(prefetched).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&decoded).unwrap(),
+            serde_json::from_str::<Value>(metadata).unwrap()
+        );
+    }
+}
@@ -0,0 +1,372 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Headless command line interface, for scripts and CI
+
+use std::{
+    io::Write,
+    process::ExitCode,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use conic_launcher_lib::{
+    account::{
+        offline_account, request_device_code, sign_in_microsoft, Account, LoginPayload, ACCOUNTS,
+    },
+    config::{
+        instance::{InstanceConfig, ModLoaderType},
+        read_config_file,
+    },
+    download::{InstallStep, Progress, ProgressError},
+    event::{Event, EventSink, SharedEventSink},
+    folder::MinecraftLocation,
+    install::install_instance,
+    instance::{create_instance, read_all_instances, Instance, SortBy},
+    launch::{
+        complete::verify_files,
+        process::{get_instance_status, InstanceState, InstanceStatus},
+        start_instance, Log,
+    },
+    prepare_data_folder, DATA_LOCATION,
+};
+use log::error;
+use uuid::Uuid;
+
+#[derive(Parser)]
+#[command(name = "conic-cli", version, about = "Conic Launcher without GUI")]
+struct Cli {
+    /// Print the launcher logs
+    #[arg(short, long, global = true)]
+    verbose: bool,
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// List all instances
+    List,
+    /// Create an instance
+    Create {
+        name: String,
+        /// Minecraft version
+        minecraft: String,
+        #[arg(long, requires = "mod_loader_version")]
+        mod_loader: Option<ModLoader>,
+        #[arg(long, requires = "mod_loader")]
+        mod_loader_version: Option<String>,
+    },
+    /// Install the game and the mod loader of an instance
+    Install {
+        /// Name or id of the instance
+        instance: String,
+    },
+    /// Sign in with a Microsoft account in the browser
+    Login,
+    /// Launch an instance and wait for the game to exit
+    Launch {
+        /// Name or id of the instance
+        instance: String,
+        /// Name or uuid of an added account, instead of the selected account
+        #[arg(long, conflicts_with = "offline_name")]
+        account: Option<String>,
+        /// Play with an offline account instead of the selected account
+        #[arg(long)]
+        offline_name: Option<String>,
+    },
+    /// Check the client jar, libraries and assets of an instance and download the broken files
+    Verify {
+        /// Name or id of the instance
+        instance: String,
+        /// Only print the broken files
+        #[arg(long)]
+        check_only: bool,
+    },
+}
+
+#[derive(Clone, ValueEnum)]
+enum ModLoader {
+    Fabric,
+    Forge,
+    Quilt,
+    Neoforged,
+}
+
+impl From<ModLoader> for ModLoaderType {
+    fn from(value: ModLoader) -> Self {
+        match value {
+            ModLoader::Fabric => Self::Fabric,
+            ModLoader::Forge => Self::Forge,
+            ModLoader::Quilt => Self::Quilt,
+            ModLoader::Neoforged => Self::Neoforged,
+        }
+    }
+}
+
+/// Print the progress to stderr and the game output to stdout
+#[derive(Default)]
+struct TerminalEventSink {
+    speed: AtomicUsize,
+}
+
+impl EventSink for TerminalEventSink {
+    fn progress(&self, progress: Progress) {
+        let step = match progress.step {
+            InstallStep::FetchVersionInfo => "Fetching version info",
+            InstallStep::CheckFiles => "Checking files",
+            InstallStep::DownloadFiles => "Downloading",
+            InstallStep::InstallModLoader => "Installing mod loader",
+        };
+        let speed = self.speed.load(Ordering::SeqCst) / 2 / 1024;
+        let eta = progress
+            .eta
+            .map(|eta| format!(", {}:{:02} left", eta / 60, eta % 60))
+            .unwrap_or_default();
+        if progress.total == 0 {
+            eprint!("\r\x1b[2K{step}: {}", progress.completed);
+        } else if progress.total_bytes > 0 {
+            eprint!(
+                "\r\x1b[2K{step}: {}/{} files, {}/{} MiB ({speed} KiB/s{eta})",
+                progress.completed,
+                progress.total,
+                progress.completed_bytes / 1024 / 1024,
+                progress.total_bytes / 1024 / 1024
+            );
+        } else {
+            eprint!(
+                "\r\x1b[2K{step}: {}/{} ({speed} KiB/s)",
+                progress.completed, progress.total
+            );
+        }
+        let _ = std::io::stderr().flush();
+    }
+
+    fn speed(&self, speed: usize) {
+        self.speed.store(speed, Ordering::SeqCst);
+    }
+
+    fn log(&self, log: Log) {
+        println!("{}", log.content);
+    }
+
+    fn error(&self, error: ProgressError) {
+        eprintln!("\nInstallation failed at step {:?}", error.step);
+    }
+
+    fn state(&self, status: InstanceStatus) {
+        match status.state {
+            InstanceState::Starting => eprintln!("Starting {}", status.instance_id),
+            InstanceState::Running => eprintln!("The game is running, pid: {:?}", status.pid),
+            InstanceState::Exited { code } => eprintln!("The game exited with code {code:?}"),
+        }
+    }
+
+    fn emit(&self, event: Event) {
+        match event {
+            Event::InstallSuccess => eprintln!("\nInstalled successfully"),
+            Event::AccountRequired => {
+                eprintln!("No account is selected, use --account or --offline-name")
+            }
+            Event::JavaVersionMismatch(mismatch) => eprintln!(
+                "The game requires Java {}, but {} is Java {}",
+                mismatch.required,
+                mismatch.java.display(),
+                mismatch.found
+            ),
+            Event::LaunchCrashed(analysis) => {
+                for cause in analysis.causes {
+                    eprintln!(
+                        "Possible crash cause: {:?}, suspect: {}",
+                        cause.category,
+                        cause.suspect.as_deref().unwrap_or("unknown")
+                    );
+                }
+                for crash_report in analysis.crash_reports {
+                    eprintln!("Crash report: {}", crash_report.display());
+                }
+            }
+            Event::RunningDownloadTasks(_)
+            | Event::AccountsChanged
+            | Event::DownloadJobsChanged => (),
+        }
+    }
+}
+
+async fn find_instance(name_or_id: &str) -> Option<Instance> {
+    let id = Uuid::from_str(name_or_id).ok();
+    read_all_instances(SortBy::Name)
+        .await
+        .into_iter()
+        .find(|instance| Some(instance.id) == id || instance.config.name == name_or_id)
+}
+
+fn find_account(name_or_uuid: &str) -> anyhow::Result<Option<Account>> {
+    Ok(ACCOUNTS.list()?.into_iter().find(|account| {
+        account.profile.uuid == name_or_uuid || account.profile.profile_name == name_or_uuid
+    }))
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let default_filter = if cli.verbose { "info" } else { "warn" };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_filter))
+        .init();
+    let sink: SharedEventSink = Arc::new(TerminalEventSink::default());
+    prepare_data_folder().await;
+    let config = read_config_file();
+    match cli.command {
+        Commands::List => {
+            for instance in read_all_instances(SortBy::Name).await {
+                let runtime = &instance.config.runtime;
+                let mod_loader = match (&runtime.mod_loader_type, &runtime.mod_loader_version) {
+                    (Some(x), Some(version)) => format!(" {x} {version}"),
+                    _ => String::new(),
+                };
+                println!(
+                    "{}\t{}\t{}{}\t{}",
+                    instance.id,
+                    instance.config.name,
+                    runtime.minecraft,
+                    mod_loader,
+                    if instance.installed {
+                        "installed"
+                    } else {
+                        "not installed"
+                    }
+                );
+            }
+        }
+        Commands::Create {
+            name,
+            minecraft,
+            mod_loader,
+            mod_loader_version,
+        } => {
+            let mut instance_config = InstanceConfig::new(&name, &minecraft);
+            instance_config.runtime.mod_loader_type = mod_loader.map(ModLoaderType::from);
+            instance_config.runtime.mod_loader_version = mod_loader_version;
+            let instance = create_instance(instance_config).await;
+            println!("{}", instance.id);
+        }
+        Commands::Install { instance } => {
+            let Some(instance) = find_instance(&instance).await else {
+                error!("Instance {instance} not found");
+                return ExitCode::FAILURE;
+            };
+            if install_instance(instance, sink).await.is_err() {
+                return ExitCode::FAILURE;
+            }
+        }
+        Commands::Login => {
+            let device_code = match request_device_code().await {
+                Ok(x) => x,
+                Err(err) => {
+                    error!("Failed to request a device code: {err}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            eprintln!(
+                "Open {} and enter the code {}",
+                device_code.verification_uri, device_code.user_code
+            );
+            match sign_in_microsoft(LoginPayload::DeviceCode(device_code), sink.as_ref()).await {
+                Ok(account) => println!(
+                    "Signed in as {} ({})",
+                    account.profile.profile_name, account.profile.uuid
+                ),
+                Err(err) => {
+                    error!("{err}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        Commands::Launch {
+            instance,
+            account,
+            offline_name,
+        } => {
+            let Some(instance) = find_instance(&instance).await else {
+                error!("Instance {instance} not found");
+                return ExitCode::FAILURE;
+            };
+            let instance_id = instance.id;
+            let account = match (account, offline_name) {
+                (Some(name_or_uuid), _) => match find_account(&name_or_uuid) {
+                    Ok(Some(x)) => Some(x),
+                    Ok(None) => {
+                        error!("Account {name_or_uuid} not found");
+                        return ExitCode::FAILURE;
+                    }
+                    Err(err) => {
+                        error!("Failed to read the accounts: {err}");
+                        return ExitCode::FAILURE;
+                    }
+                },
+                (None, Some(name)) => match offline_account(&name) {
+                    Ok(x) => Some(x),
+                    Err(err) => {
+                        error!("{err}");
+                        return ExitCode::FAILURE;
+                    }
+                },
+                (None, None) => None,
+            };
+            let game_thread = match start_instance(config, instance, account, sink).await {
+                Ok(x) => x,
+                Err(_) => return ExitCode::FAILURE,
+            };
+            let _ = game_thread.join();
+            let code = get_instance_status(instance_id).and_then(|status| match status.state {
+                InstanceState::Exited { code } => code,
+                _ => None,
+            });
+            return match code {
+                Some(0) => ExitCode::SUCCESS,
+                _ => ExitCode::FAILURE,
+            };
+        }
+        Commands::Verify {
+            instance,
+            check_only,
+        } => {
+            let Some(instance) = find_instance(&instance).await else {
+                error!("Instance {instance} not found");
+                return ExitCode::FAILURE;
+            };
+            let report = match verify_files(
+                &instance,
+                &MinecraftLocation::new(&DATA_LOCATION.root),
+                !check_only,
+                sink,
+            )
+            .await
+            {
+                Ok(report) => report,
+                Err(err) => {
+                    error!("Failed to verify {}: {err}", instance.config.name);
+                    return ExitCode::FAILURE;
+                }
+            };
+            for broken in &report.broken {
+                println!("{:?}: {}", broken.problem, broken.file.display());
+            }
+            eprintln!(
+                "Checked {} files of {}, {} broken",
+                report.checked,
+                instance.config.name,
+                report.broken.len()
+            );
+            if !report.broken.is_empty() && !report.repaired {
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
@@ -2,14 +2,28 @@
 // Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
 // SPDX-License-Identifier: GPL-3.0-only
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
+use crate::download::mirror::{default_mirrors, Mirror};
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct DownloadConfig {
     #[serde(default = "default_max_connection")]
     pub max_connection: usize,
     #[serde(default)]
     pub max_download_speed: usize,
+    /// Host -> bytes per second, like `bmclapi2.bangbang93.com`
+    #[serde(default)]
+    pub host_speed_limits: HashMap<String, usize>,
+    /// Share the files with a sha1 between data folders, see [`crate::download::cache`]
+    #[serde(default)]
+    pub shared_cache: bool,
+    /// Tried in order until one of them works. Only the official servers by default,
+    /// BMCLAPI is used after `{ type = "Bmclapi" }` is added
+    #[serde(default = "default_mirrors")]
+    pub mirrors: Vec<Mirror>,
 }
 
 fn default_max_connection() -> usize {
@@ -21,6 +35,9 @@ impl Default for DownloadConfig {
         Self {
             max_connection: default_max_connection(),
             max_download_speed: 0,
+            host_speed_limits: HashMap::new(),
+            shared_cache: false,
+            mirrors: default_mirrors(),
         }
     }
 }
@@ -79,6 +79,10 @@ pub struct InstanceLaunchConfig {
     pub(crate) gc: Option<GC>,
 
     pub(crate) launcher_name: Option<String>,
+
+    /// Path of the java executable, `auto` to use the runtime required by the game
+    pub(crate) java_path: Option<String>,
+
     pub wrap_command: Option<String>,
 
     pub execute_before_launch: Option<String>,
@@ -78,6 +78,10 @@ pub struct LaunchConfig {
     #[serde(default = "default_launcher_name")]
     pub(crate) launcher_name: String,
 
+    #[serde(default = "default_java_path")]
+    /// Path of the java executable, `auto` to use the runtime required by the game
+    pub(crate) java_path: String,
+
     #[serde(default)]
     /// Add this to the front of launch command
     pub wrap_command: String,
@@ -95,6 +99,10 @@ pub struct LaunchConfig {
     #[serde(default)]
     /// Skip check game file integrity
     pub skip_check_files: bool,
+
+    #[serde(default)]
+    /// Write the launch command to a script in the instance folder for debugging
+    pub export_launch_script: bool,
 }
 
 fn default_max_memory() -> usize {
@@ -111,6 +119,10 @@ fn default_launcher_name() -> String {
     "Conic_Launcher".to_string()
 }
 
+fn default_java_path() -> String {
+    "auto".to_string()
+}
+
 impl Default for LaunchConfig {
     fn default() -> Self {
         Self {
@@ -128,11 +140,13 @@ impl Default for LaunchConfig {
             extra_class_paths: String::new(),
             gc: GC::default(),
             launcher_name: default_launcher_name(),
+            java_path: default_java_path(),
             wrap_command: String::new(),
             execute_after_launch: String::new(),
             execute_before_launch: String::new(),
             skip_refresh_account: false,
             skip_check_files: false,
+            export_launch_script: false,
         }
     }
 }
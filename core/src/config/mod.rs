@@ -5,7 +5,7 @@
 use log::{debug, info};
 use serde::{Deserialize, Serialize};
 
-use crate::{account::get_accounts, Storage, DATA_LOCATION};
+use crate::{account::get_accounts, download::configure, Storage, DATA_LOCATION};
 
 pub mod download;
 pub mod instance;
@@ -182,12 +182,14 @@ pub fn read_config_file() -> Config {
     let result = toml::from_str::<Config>(&String::from_utf8(data).unwrap()).unwrap();
     let write_back_data = toml::to_string_pretty(&result).unwrap();
     std::fs::write(config_file_path, write_back_data).unwrap();
+    configure(&result.download);
     result
 }
 
 #[tauri::command]
 pub fn update_config(storage: tauri::State<'_, Storage>, config: Config) {
     let mut storage_config = storage.config.lock().unwrap();
+    configure(&config.download);
     *storage_config = config;
     debug!("Configuration was synchronized with the front end");
 }
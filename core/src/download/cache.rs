@@ -0,0 +1,361 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Files with a sha1 are kept in `<cache>/objects/<sha1[..2]>/<sha1>`, shared by all data
+//! folders. Downloads are hardlinked (or copied) from here before going to the network.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{File, OpenOptions},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use log::{debug, error, info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::{calculate_sha1_from_read, Download};
+use crate::DATA_LOCATION;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static STORE: Lazy<ObjectStore> =
+    Lazy::new(|| ObjectStore::new(DATA_LOCATION.cache.join("objects")));
+
+/// Called when the config is loaded or changed
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+/// Link the file from the store, returns `false` if it has to be downloaded
+pub fn restore(task: &Download) -> bool {
+    ENABLED.load(Ordering::SeqCst) && STORE.restore(task)
+}
+
+/// Add a downloaded and verified file to the store
+pub fn insert(task: &Download) {
+    if ENABLED.load(Ordering::SeqCst) {
+        STORE.insert(task);
+    }
+}
+
+pub fn save() {
+    if ENABLED.load(Ordering::SeqCst) {
+        if let Err(err) = STORE.save() {
+            warn!("Failed to save the object references: {err}");
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct GarbageCollection {
+    pub removed: usize,
+    pub freed_bytes: u64,
+}
+
+/// Remove the objects no file links to anymore
+#[tauri::command(async)]
+pub async fn collect_cache_garbage() -> Result<GarbageCollection, ()> {
+    let result = tokio::task::spawn_blocking(|| STORE.collect_garbage())
+        .await
+        .unwrap();
+    result.map_err(|err| {
+        error!("Failed to collect the cache garbage: {err}");
+    })
+}
+
+type References = HashMap<String, HashSet<PathBuf>>;
+
+pub struct ObjectStore {
+    root: PathBuf,
+    /// sha1 -> files restored from or added as the object
+    references: Mutex<References>,
+}
+
+impl ObjectStore {
+    pub fn new(root: PathBuf) -> Self {
+        let references = read_references(&root);
+        Self {
+            root,
+            references: Mutex::new(references),
+        }
+    }
+
+    fn object_path(&self, sha1: &str) -> Option<PathBuf> {
+        if sha1.len() != 40 || !sha1.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let sha1 = sha1.to_ascii_lowercase();
+        Some(self.root.join(&sha1[..2]).join(sha1))
+    }
+
+    fn add_reference(&self, sha1: &str, file: &Path) {
+        self.references
+            .lock()
+            .unwrap()
+            .entry(sha1.to_ascii_lowercase())
+            .or_default()
+            .insert(file.to_path_buf());
+    }
+
+    pub fn restore(&self, task: &Download) -> bool {
+        let Some(sha1) = &task.sha1 else {
+            return false;
+        };
+        let Some(object) = self.object_path(sha1) else {
+            return false;
+        };
+        let Ok(mut file) = std::fs::File::open(&object) else {
+            return false;
+        };
+        // A hardlinked file changed in place changes the object too
+        if !calculate_sha1_from_read(&mut file).eq_ignore_ascii_case(sha1) {
+            warn!("Removing the broken object {}", object.display());
+            let _ = std::fs::remove_file(&object);
+            return false;
+        }
+        if let Some(parent) = task.file.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        // The existing file is only replaced once the object has been linked or copied
+        let temp = task.file.with_file_name(format!(".{}.tmp", Uuid::new_v4()));
+        let result = link_or_copy(&object, &temp).and_then(|_| std::fs::rename(&temp, &task.file));
+        if let Err(err) = result {
+            let _ = std::fs::remove_file(&temp);
+            warn!(
+                "Failed to restore {} from the cache: {err}",
+                task.file.display()
+            );
+            return false;
+        }
+        debug!("Restored {} from the cache", task.file.display());
+        self.add_reference(sha1, &task.file);
+        true
+    }
+
+    pub fn insert(&self, task: &Download) {
+        let Some(sha1) = &task.sha1 else {
+            return;
+        };
+        let Some(object) = self.object_path(sha1) else {
+            return;
+        };
+        if !object.exists() {
+            let temp = object.with_file_name(format!("{}.tmp", Uuid::new_v4()));
+            let result = std::fs::create_dir_all(object.parent().unwrap())
+                .and_then(|_| link_or_copy(&task.file, &temp))
+                .and_then(|_| std::fs::rename(&temp, &object));
+            if let Err(err) = result {
+                let _ = std::fs::remove_file(&temp);
+                warn!("Failed to add {} to the cache: {err}", task.file.display());
+                return;
+            }
+        }
+        self.add_reference(sha1, &task.file);
+    }
+
+    /// The launcher and `conic-cli` share the store, so the references saved by the other
+    /// process are merged before writing
+    pub fn save(&self) -> anyhow::Result<()> {
+        let _lock = self.lock_file()?;
+        let mut references = self.references.lock().unwrap();
+        merge_references(&mut references, read_references(&self.root));
+        self.write(&references)
+    }
+
+    fn write(&self, references: &References) -> anyhow::Result<()> {
+        let data = serde_json::to_vec(references)?;
+        let temp = self
+            .root
+            .join(format!("references.json.{}.tmp", Uuid::new_v4()));
+        let result = std::fs::write(&temp, data)
+            .and_then(|_| std::fs::rename(&temp, self.root.join("references.json")));
+        if result.is_err() {
+            let _ = std::fs::remove_file(&temp);
+        }
+        result?;
+        Ok(())
+    }
+
+    /// Blocks until the other process has saved, released when the file is dropped
+    fn lock_file(&self) -> anyhow::Result<File> {
+        std::fs::create_dir_all(&self.root)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.root.join("references.json.lock"))?;
+        file.lock()?;
+        Ok(file)
+    }
+
+    /// An object is still used if one of its files exists with the same size
+    pub fn collect_garbage(&self) -> anyhow::Result<GarbageCollection> {
+        let mut result = GarbageCollection::default();
+        let Ok(folders) = std::fs::read_dir(&self.root) else {
+            return Ok(result);
+        };
+        let _lock = self.lock_file()?;
+        let mut references = self.references.lock().unwrap();
+        // Objects only used by the other process are kept too
+        merge_references(&mut references, read_references(&self.root));
+        for folder in folders.flatten().filter(|entry| entry.path().is_dir()) {
+            for entry in std::fs::read_dir(folder.path())?.flatten() {
+                let sha1 = entry.file_name().to_string_lossy().to_string();
+                let len = entry.metadata()?.len();
+                let used = references.get_mut(&sha1).is_some_and(|files| {
+                    files.retain(|file| std::fs::metadata(file).is_ok_and(|x| x.len() == len));
+                    !files.is_empty()
+                });
+                if !used {
+                    std::fs::remove_file(entry.path())?;
+                    result.removed += 1;
+                    result.freed_bytes += len;
+                }
+            }
+        }
+        references.retain(|sha1, files| {
+            !files.is_empty() && self.object_path(sha1).is_some_and(|object| object.exists())
+        });
+        self.write(&references)?;
+        drop(references);
+        info!(
+            "Removed {} objects from the cache, freed {} bytes",
+            result.removed, result.freed_bytes
+        );
+        Ok(result)
+    }
+}
+
+fn read_references(root: &Path) -> References {
+    std::fs::read(root.join("references.json"))
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+fn merge_references(references: &mut References, saved: References) {
+    for (sha1, files) in saved {
+        references.entry(sha1).or_default().extend(files);
+    }
+}
+
+fn link_or_copy(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::hard_link(from, to).or_else(|_| std::fs::copy(from, to).map(|_| ()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::temp_dir::TempDir;
+
+    fn download(root: &Path, name: &str, content: &str) -> Download {
+        let file = root.join(name);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(&file, content).unwrap();
+        Download {
+            url: format!("https://example.com/{name}"),
+            file,
+            sha1: Some(sha1_smol::Sha1::from(content).digest().to_string()),
+            size: Some(content.len() as u64),
+        }
+    }
+
+    #[test]
+    fn restore_into_another_folder() {
+        let root = TempDir::new();
+        let store = ObjectStore::new(root.join("objects"));
+        let task = download(&root, "a/.minecraft/assets/object", "asset");
+        store.insert(&task);
+        store.save().unwrap();
+
+        let store = ObjectStore::new(root.join("objects"));
+        let mut other = task.clone();
+        other.file = root.join("b/.minecraft/assets/object");
+        assert!(store.restore(&other));
+        assert_eq!(std::fs::read_to_string(&other.file).unwrap(), "asset");
+
+        other.sha1 = Some("0".repeat(40));
+        assert!(!store.restore(&other));
+    }
+
+    #[test]
+    fn restore_over_an_existing_file() {
+        let root = TempDir::new();
+        let store = ObjectStore::new(root.join("objects"));
+        let task = download(&root, "a/options.txt", "cached");
+        store.insert(&task);
+        let mut other = task.clone();
+        other.file = root.join("b/options.txt");
+        std::fs::create_dir_all(root.join("b")).unwrap();
+        std::fs::write(&other.file, "old").unwrap();
+        assert!(store.restore(&other));
+        assert_eq!(std::fs::read_to_string(&other.file).unwrap(), "cached");
+        let names: Vec<_> = std::fs::read_dir(root.join("b"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["options.txt"]);
+    }
+
+    #[test]
+    fn references_of_other_processes_are_kept() {
+        let root = TempDir::new();
+        let launcher = ObjectStore::new(root.join("objects"));
+        let cli = ObjectStore::new(root.join("objects"));
+        let a = download(&root, "a/library.jar", "a");
+        let b = download(&root, "b/library.jar", "b");
+        launcher.insert(&a);
+        cli.insert(&b);
+        launcher.save().unwrap();
+        cli.save().unwrap();
+        let references = ObjectStore::new(root.join("objects"))
+            .references
+            .into_inner()
+            .unwrap();
+        assert!(references[a.sha1.as_ref().unwrap()].contains(&a.file));
+        assert!(references[b.sha1.as_ref().unwrap()].contains(&b.file));
+    }
+
+    #[test]
+    fn broken_objects_are_not_restored() {
+        let root = TempDir::new();
+        let store = ObjectStore::new(root.join("objects"));
+        let task = download(&root, "a/library.jar", "library");
+        store.insert(&task);
+        let object = store.object_path(task.sha1.as_ref().unwrap()).unwrap();
+        std::fs::remove_file(&object).unwrap();
+        std::fs::write(&object, "changed").unwrap();
+        let mut other = task.clone();
+        other.file = root.join("b/library.jar");
+        assert!(!store.restore(&other));
+        assert!(!object.exists());
+    }
+
+    #[test]
+    fn collect_unreferenced_objects() {
+        let root = TempDir::new();
+        let store = ObjectStore::new(root.join("objects"));
+        let kept = download(&root, "a/kept", "kept");
+        let removed = download(&root, "a/removed", "removed");
+        store.insert(&kept);
+        store.insert(&removed);
+        std::fs::remove_file(&removed.file).unwrap();
+        assert_eq!(
+            store.collect_garbage().unwrap(),
+            GarbageCollection {
+                removed: 1,
+                freed_bytes: 7,
+            }
+        );
+        let object_of = |task: &Download| store.object_path(task.sha1.as_ref().unwrap()).unwrap();
+        assert!(object_of(&kept).exists());
+        assert!(!object_of(&removed).exists());
+    }
+}
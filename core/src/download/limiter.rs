@@ -0,0 +1,237 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Token buckets shared by all downloads. Every chunk takes its size from the global bucket
+//! and from the bucket of its host, and waits until both of them are refilled.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+struct TokenBucket {
+    /// Bytes per second, the bucket also holds at most one second of bytes
+    rate: u64,
+    /// Negative when chunks have taken more than available, the next ones wait for the debt
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u64, now: Instant) -> Self {
+        Self {
+            rate,
+            tokens: rate as f64,
+            updated: now,
+        }
+    }
+
+    fn set_rate(&mut self, rate: u64) {
+        self.rate = rate;
+        self.tokens = self.tokens.min(rate as f64);
+    }
+
+    /// Take `bytes` and return how long to wait before using them
+    fn take(&mut self, bytes: u64, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate as f64).min(self.rate as f64);
+        self.updated = now;
+        self.tokens -= bytes as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate as f64)
+        }
+    }
+}
+
+#[derive(Default)]
+struct Buckets {
+    global: Option<TokenBucket>,
+    hosts: HashMap<String, TokenBucket>,
+}
+
+pub struct BandwidthLimiter {
+    clock: Box<dyn Clock>,
+    buckets: Mutex<Buckets>,
+}
+
+impl BandwidthLimiter {
+    pub fn new(clock: Box<dyn Clock>) -> Self {
+        Self {
+            clock,
+            buckets: Mutex::new(Buckets::default()),
+        }
+    }
+
+    /// Bytes per second of all downloads, 0 for no limit
+    pub fn set_limit(&self, limit: usize) {
+        let now = self.clock.now();
+        let mut buckets = self.buckets.lock().unwrap();
+        buckets.global = match (buckets.global.take(), limit as u64) {
+            (_, 0) => None,
+            (Some(mut bucket), rate) => {
+                bucket.set_rate(rate);
+                Some(bucket)
+            }
+            (None, rate) => Some(TokenBucket::new(rate, now)),
+        };
+    }
+
+    /// Bytes per second of the downloads from each host, hosts not in the map are not limited
+    pub fn set_host_limits(&self, limits: &HashMap<String, usize>) {
+        let now = self.clock.now();
+        let mut buckets = self.buckets.lock().unwrap();
+        buckets
+            .hosts
+            .retain(|host, _| limits.get(host).is_some_and(|limit| *limit > 0));
+        for (host, limit) in limits.iter().filter(|(_, limit)| **limit > 0) {
+            buckets
+                .hosts
+                .entry(host.clone())
+                .and_modify(|bucket| bucket.set_rate(*limit as u64))
+                .or_insert_with(|| TokenBucket::new(*limit as u64, now));
+        }
+    }
+
+    /// Take `bytes` from the buckets and return how long to wait before using them
+    pub fn reserve(&self, host: &str, bytes: usize) -> Duration {
+        let now = self.clock.now();
+        let mut buckets = self.buckets.lock().unwrap();
+        let global = buckets
+            .global
+            .as_mut()
+            .map_or(Duration::ZERO, |bucket| bucket.take(bytes as u64, now));
+        let host = buckets
+            .hosts
+            .get_mut(host)
+            .map_or(Duration::ZERO, |bucket| bucket.take(bytes as u64, now));
+        global.max(host)
+    }
+
+    pub async fn acquire(&self, host: &str, bytes: usize) {
+        let wait = self.reserve(host, bytes);
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[derive(Clone)]
+    struct MockClock(Arc<Mutex<Instant>>);
+
+    impl MockClock {
+        fn new() -> Self {
+            Self(Arc::new(Mutex::new(Instant::now())))
+        }
+
+        fn advance(&self, duration: Duration) {
+            *self.0.lock().unwrap() += duration;
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn limiter() -> (BandwidthLimiter, MockClock) {
+        let clock = MockClock::new();
+        (BandwidthLimiter::new(Box::new(clock.clone())), clock)
+    }
+
+    #[test]
+    fn unlimited_by_default() {
+        let (limiter, _) = limiter();
+        assert_eq!(limiter.reserve("example.com", 1 << 30), Duration::ZERO);
+    }
+
+    #[test]
+    fn wait_for_the_debt() {
+        let (limiter, clock) = limiter();
+        limiter.set_limit(1000);
+        // One second of burst is available at first
+        assert_eq!(limiter.reserve("example.com", 1000), Duration::ZERO);
+        assert_eq!(
+            limiter.reserve("example.com", 500),
+            Duration::from_millis(500)
+        );
+        // Concurrent downloads queue up behind each other
+        assert_eq!(limiter.reserve("example.com", 500), Duration::from_secs(1));
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(limiter.reserve("example.com", 0), Duration::ZERO);
+        // Idle time doesn't save more than one second of bytes
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(
+            limiter.reserve("example.com", 1500),
+            Duration::from_millis(500)
+        );
+    }
+
+    #[test]
+    fn limits_below_1024_bytes_work() {
+        let (limiter, _) = limiter();
+        limiter.set_limit(100);
+        assert_eq!(limiter.reserve("example.com", 100), Duration::ZERO);
+        assert_eq!(
+            limiter.reserve("example.com", 50),
+            Duration::from_millis(500)
+        );
+    }
+
+    #[test]
+    fn per_host_limits() {
+        let (limiter, clock) = limiter();
+        limiter.set_host_limits(&HashMap::from([("slow.example.com".to_string(), 100)]));
+        assert_eq!(
+            limiter.reserve("slow.example.com", 200),
+            Duration::from_secs(1)
+        );
+        assert_eq!(limiter.reserve("fast.example.com", 200), Duration::ZERO);
+        // The stricter one of the global and the host limit is used
+        limiter.set_limit(1000);
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(
+            limiter.reserve("slow.example.com", 300),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            limiter.reserve("fast.example.com", 900),
+            Duration::from_millis(200)
+        );
+    }
+
+    #[test]
+    fn change_limits_at_runtime() {
+        let (limiter, clock) = limiter();
+        limiter.set_limit(1000);
+        assert_eq!(limiter.reserve("example.com", 1000), Duration::ZERO);
+        // The stored bytes are clamped to the new rate
+        limiter.set_limit(100);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(limiter.reserve("example.com", 200), Duration::from_secs(1));
+        limiter.set_limit(0);
+        assert_eq!(limiter.reserve("example.com", 1 << 30), Duration::ZERO);
+    }
+}
@@ -0,0 +1,597 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! All downloads are jobs in one queue, so installing several instances at the same time
+//! shares the connections. Jobs are saved to `download_jobs.json`, the ones not finished
+//! when the launcher exits can be resumed after a restart.
+
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Semaphore, SemaphorePermit};
+use uuid::Uuid;
+
+use super::{download_files, download_resumable, Download, Transfers, MAX_ATTEMPTS};
+use crate::{
+    config::download::DownloadConfig,
+    event::{Event, EventSink, SharedEventSink, TauriEventSink},
+    install::install_instance,
+    instance::{read_all_instances, SortBy},
+    DATA_LOCATION,
+};
+
+pub static DOWNLOADS: Lazy<DownloadManager> =
+    Lazy::new(|| DownloadManager::load(DATA_LOCATION.root.join("download_jobs.json")));
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum JobState {
+    /// Waiting for a connection
+    Queued,
+    Running,
+    Paused,
+    /// The launcher exited before the job finished
+    Interrupted,
+    Cancelled,
+    Failed,
+    Completed,
+}
+
+impl JobState {
+    fn is_finished(&self) -> bool {
+        matches!(self, Self::Cancelled | Self::Failed | Self::Completed)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct JobInfo {
+    pub id: Uuid,
+    pub name: String,
+    pub state: JobState,
+    pub completed: usize,
+    pub total: usize,
+    /// Resuming an interrupted job installs this instance again
+    pub instance_id: Option<Uuid>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct SavedJob {
+    info: JobInfo,
+    /// Empty if the job belongs to an instance, it is installed again to resume
+    #[serde(default)]
+    downloads: Vec<Download>,
+}
+
+pub struct Job {
+    info: Mutex<JobInfo>,
+    downloads: Vec<Download>,
+    sink: SharedEventSink,
+    paused: AtomicBool,
+    cancelled: AtomicBool,
+    failed: AtomicBool,
+}
+
+impl Job {
+    pub fn id(&self) -> Uuid {
+        self.info.lock().unwrap().id
+    }
+
+    /// Running downloads stop at the next chunk, the part files are kept for resuming
+    fn should_stop(&self) -> bool {
+        self.paused.load(Ordering::SeqCst) || self.is_aborted()
+    }
+
+    fn is_aborted(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst) || self.failed.load(Ordering::SeqCst)
+    }
+}
+
+pub struct DownloadManager {
+    path: PathBuf,
+    jobs: Mutex<Vec<Arc<Job>>>,
+    /// Jobs of the last run that didn't finish
+    interrupted: Mutex<Vec<SavedJob>>,
+    connections: Semaphore,
+    connection_limit: AtomicUsize,
+    /// Permits to forget when they are released, left after lowering the limit
+    excess_permits: Mutex<usize>,
+    /// Held while saving, so an older list never replaces a newer one
+    saving: Mutex<()>,
+}
+
+#[tauri::command]
+pub fn list_download_jobs() -> Vec<JobInfo> {
+    DOWNLOADS.list()
+}
+
+#[tauri::command]
+pub fn pause_download_job(id: Uuid) -> Result<(), ()> {
+    DOWNLOADS.pause(id).map_err(|err| {
+        error!("Failed to pause the download job {id}: {err}");
+    })
+}
+
+#[tauri::command]
+pub fn cancel_download_job(id: Uuid) -> Result<(), ()> {
+    DOWNLOADS.cancel(id).map_err(|err| {
+        error!("Failed to cancel the download job {id}: {err}");
+    })
+}
+
+/// Resume a paused job, or start an interrupted job of the last run again
+#[tauri::command(async)]
+pub async fn resume_download_job(id: Uuid) -> Result<(), ()> {
+    if DOWNLOADS.resume(id).is_ok() {
+        return Ok(());
+    }
+    let job = DOWNLOADS
+        .take_interrupted(id, &TauriEventSink)
+        .ok_or_else(|| {
+            error!("No download job {id}");
+        })?;
+    info!("Restarting the interrupted download job {}", job.info.name);
+    match job.info.instance_id {
+        Some(instance_id) => {
+            let instance = read_all_instances(SortBy::Name)
+                .await
+                .into_iter()
+                .find(|instance| instance.id == instance_id)
+                .ok_or_else(|| {
+                    error!("The instance {instance_id} has been deleted");
+                })?;
+            tokio::spawn(async move {
+                let _ = install_instance(instance, TauriEventSink::shared()).await;
+            });
+        }
+        None => {
+            tokio::spawn(async move {
+                let result = download_files(
+                    &job.info.name,
+                    None,
+                    job.downloads,
+                    TauriEventSink::shared(),
+                    false,
+                    false,
+                )
+                .await;
+                if let Err(err) = result {
+                    error!("Failed to download {}: {err}", job.info.name);
+                }
+            });
+        }
+    }
+    Ok(())
+}
+
+impl DownloadManager {
+    pub fn load(path: PathBuf) -> Self {
+        let saved: Vec<SavedJob> = std::fs::read(&path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        let interrupted = saved
+            .into_iter()
+            .filter(|job| !job.info.state.is_finished())
+            .map(|mut job| {
+                job.info.state = JobState::Interrupted;
+                job
+            })
+            .collect();
+        let connection_limit = DownloadConfig::default().max_connection;
+        Self {
+            path,
+            jobs: Mutex::new(vec![]),
+            interrupted: Mutex::new(interrupted),
+            connections: Semaphore::new(connection_limit),
+            connection_limit: AtomicUsize::new(connection_limit),
+            excess_permits: Mutex::new(0),
+            saving: Mutex::new(()),
+        }
+    }
+
+    /// Change the number of connections shared by all jobs.
+    ///
+    /// Permits in use can't be forgotten, so the limit is reached when they are released
+    pub fn set_connection_limit(&self, limit: usize) {
+        let limit = limit.max(1);
+        let mut excess = self.excess_permits.lock().unwrap();
+        let current = self.connection_limit.swap(limit, Ordering::SeqCst);
+        if limit > current {
+            let added = limit - current;
+            let kept = added.min(*excess);
+            *excess -= kept;
+            self.connections.add_permits(added - kept);
+        } else {
+            let removed = current - limit;
+            *excess += removed - self.connections.forget_permits(removed);
+        }
+    }
+
+    /// Give the permit back, or forget it if the limit has been lowered
+    fn release(&self, permit: SemaphorePermit<'_>) {
+        let mut excess = self.excess_permits.lock().unwrap();
+        if *excess > 0 {
+            *excess -= 1;
+            permit.forget();
+        }
+    }
+
+    pub fn connection_limit(&self) -> usize {
+        self.connection_limit.load(Ordering::SeqCst).max(1)
+    }
+
+    pub fn list(&self) -> Vec<JobInfo> {
+        let mut result: Vec<JobInfo> = self
+            .interrupted
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|job| job.info.clone())
+            .collect();
+        result.extend(
+            self.jobs
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|job| job.info.lock().unwrap().clone()),
+        );
+        result
+    }
+
+    pub fn add_job(
+        &self,
+        name: &str,
+        instance_id: Option<Uuid>,
+        downloads: Vec<Download>,
+        sink: SharedEventSink,
+    ) -> Arc<Job> {
+        let job = Arc::new(Job {
+            info: Mutex::new(JobInfo {
+                id: Uuid::new_v4(),
+                name: name.to_string(),
+                state: JobState::Queued,
+                completed: 0,
+                total: downloads.len(),
+                instance_id,
+            }),
+            downloads,
+            sink,
+            paused: AtomicBool::new(false),
+            cancelled: AtomicBool::new(false),
+            failed: AtomicBool::new(false),
+        });
+        // A new install of the same instance replaces the interrupted one
+        if instance_id.is_some() {
+            self.interrupted
+                .lock()
+                .unwrap()
+                .retain(|saved| saved.info.instance_id != instance_id);
+        }
+        self.jobs.lock().unwrap().push(job.clone());
+        self.changed(job.sink.as_ref());
+        job
+    }
+
+    pub fn pause(&self, id: Uuid) -> anyhow::Result<()> {
+        let job = self.find(id)?;
+        job.paused.store(true, Ordering::SeqCst);
+        self.set_state(&job, JobState::Paused);
+        Ok(())
+    }
+
+    pub fn resume(&self, id: Uuid) -> anyhow::Result<()> {
+        let job = self.find(id)?;
+        if !job.paused.swap(false, Ordering::SeqCst) {
+            return Err(anyhow!("The job is not paused"));
+        }
+        self.set_state(&job, JobState::Queued);
+        Ok(())
+    }
+
+    pub fn cancel(&self, id: Uuid) -> anyhow::Result<()> {
+        if let Ok(job) = self.find(id) {
+            job.cancelled.store(true, Ordering::SeqCst);
+            self.set_state(&job, JobState::Cancelled);
+            return Ok(());
+        }
+        self.take_interrupted(id, &TauriEventSink)
+            .map(|_| ())
+            .ok_or(anyhow!("No download job {id}"))
+    }
+
+    fn take_interrupted(&self, id: Uuid, sink: &dyn EventSink) -> Option<SavedJob> {
+        let mut interrupted = self.interrupted.lock().unwrap();
+        let index = interrupted.iter().position(|job| job.info.id == id)?;
+        let job = interrupted.remove(index);
+        drop(interrupted);
+        self.changed(sink);
+        Some(job)
+    }
+
+    fn find(&self, id: Uuid) -> anyhow::Result<Arc<Job>> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|job| job.id() == id && !job.is_aborted())
+            .cloned()
+            .ok_or(anyhow!("No running download job {id}"))
+    }
+
+    /// Download one file of the job, waiting while the job is paused.
+    /// Returns `false` if the job has been cancelled or has failed.
+    pub async fn download(
+        &self,
+        job: &Job,
+        task: &Download,
+        transfers: &Transfers,
+    ) -> anyhow::Result<bool> {
+        let mut retried = 0;
+        loop {
+            while job.paused.load(Ordering::SeqCst) && !job.is_aborted() {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+            }
+            if job.is_aborted() {
+                return Ok(false);
+            }
+            let permit = self.connections.acquire().await?;
+            if job.should_stop() {
+                self.release(permit);
+                continue;
+            }
+            if job.info.lock().unwrap().state == JobState::Queued {
+                self.set_state(job, JobState::Running);
+            }
+            let result = download_resumable(task, transfers, &|| job.should_stop()).await;
+            self.release(permit);
+            match result {
+                Ok(true) => {
+                    job.info.lock().unwrap().completed += 1;
+                    return Ok(true);
+                }
+                // Paused or aborted, checked again at the top
+                Ok(false) => continue,
+                Err(err) => {
+                    retried += 1;
+                    warn!("Downloaded failed: {}, retried: {retried}", task.url);
+                    if retried >= MAX_ATTEMPTS {
+                        job.failed.store(true, Ordering::SeqCst);
+                        return Err(err);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Called when all downloads of the job have returned, the job is removed from the list
+    pub fn finish(&self, job: &Arc<Job>) -> JobState {
+        let state = if job.cancelled.load(Ordering::SeqCst) {
+            JobState::Cancelled
+        } else if job.failed.load(Ordering::SeqCst) {
+            JobState::Failed
+        } else {
+            JobState::Completed
+        };
+        job.info.lock().unwrap().state = state;
+        self.jobs.lock().unwrap().retain(|x| !Arc::ptr_eq(x, job));
+        self.changed(job.sink.as_ref());
+        state
+    }
+
+    fn set_state(&self, job: &Job, state: JobState) {
+        job.info.lock().unwrap().state = state;
+        self.changed(job.sink.as_ref());
+    }
+
+    fn changed(&self, sink: &dyn EventSink) {
+        if let Err(err) = self.save() {
+            warn!("Failed to save the download jobs: {err}");
+        }
+        sink.emit(Event::DownloadJobsChanged);
+    }
+
+    /// Only unfinished jobs are saved, the file is replaced by renaming so a crash while
+    /// writing doesn't lose them
+    fn save(&self) -> anyhow::Result<()> {
+        let _saving = self.saving.lock().unwrap();
+        let mut jobs = self.interrupted.lock().unwrap().clone();
+        jobs.extend(self.jobs.lock().unwrap().iter().map(|job| {
+            let info = job.info.lock().unwrap().clone();
+            // An install job can have thousands of assets, they are listed again when resuming
+            let downloads = match info.instance_id {
+                Some(_) => vec![],
+                None => job.downloads.clone(),
+            };
+            SavedJob { info, downloads }
+        }));
+        jobs.retain(|job| !job.info.state.is_finished());
+        if jobs.is_empty() {
+            let _ = std::fs::remove_file(&self.path);
+            return Ok(());
+        }
+        let temp = self
+            .path
+            .with_extension(format!("json.{}.tmp", Uuid::new_v4()));
+        let result = std::fs::write(&temp, serde_json::to_vec(&jobs)?)
+            .and_then(|_| std::fs::rename(&temp, &self.path));
+        if result.is_err() {
+            let _ = std::fs::remove_file(&temp);
+        }
+        result?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event::{RecordedEvent, RecordingEventSink},
+        utils::{
+            mock_server::{MockServer, Response},
+            temp_dir::TempDir,
+        },
+    };
+
+    fn task(server: &MockServer, dir: &std::path::Path) -> Download {
+        Download {
+            url: format!("{}/client.jar", server.url),
+            file: dir.join("client.jar"),
+            sha1: None,
+            size: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn paused_job_waits_for_resume() {
+        let server = MockServer::start(|_| Response::new(200, "jar"));
+        let dir = TempDir::new();
+        let manager = DownloadManager::load(dir.join("download_jobs.json"));
+        let task = task(&server, &dir);
+        let sink = Arc::new(RecordingEventSink::default());
+        let job = manager.add_job("test", None, vec![task.clone()], sink.clone());
+        manager.pause(job.id()).unwrap();
+        let transfers = Transfers::default();
+        let (result, _) = tokio::join!(manager.download(&job, &task, &transfers), async {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            assert!(!task.file.exists());
+            assert_eq!(manager.list()[0].state, JobState::Paused);
+            manager.resume(job.id()).unwrap();
+        });
+        assert!(result.unwrap());
+        assert_eq!(std::fs::read_to_string(&task.file).unwrap(), "jar");
+        assert_eq!(manager.finish(&job), JobState::Completed);
+        assert!(manager.list().is_empty());
+        assert!(sink
+            .events()
+            .iter()
+            .any(|event| matches!(event, RecordedEvent::Event(Event::DownloadJobsChanged))));
+    }
+
+    #[tokio::test]
+    async fn cancelled_job_stops() {
+        let server = MockServer::start(|_| Response::new(200, "jar"));
+        let dir = TempDir::new();
+        let manager = DownloadManager::load(dir.join("download_jobs.json"));
+        let task = task(&server, &dir);
+        let job = manager.add_job(
+            "test",
+            None,
+            vec![task.clone()],
+            Arc::new(RecordingEventSink::default()),
+        );
+        manager.pause(job.id()).unwrap();
+        manager.cancel(job.id()).unwrap();
+        let result = manager.download(&job, &task, &Transfers::default()).await;
+        assert!(!result.unwrap());
+        assert!(!task.file.exists());
+        assert_eq!(manager.finish(&job), JobState::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn shrink_the_limit_during_a_running_job() {
+        let server = MockServer::start(|_| {
+            std::thread::sleep(Duration::from_millis(500));
+            Response::new(200, "jar")
+        });
+        let dir = TempDir::new();
+        let manager = DownloadManager::load(dir.join("download_jobs.json"));
+        manager.set_connection_limit(2);
+        let tasks: Vec<_> = ["a.jar", "b.jar"]
+            .iter()
+            .map(|name| Download {
+                url: format!("{}/{name}", server.url),
+                file: dir.join(name),
+                sha1: None,
+                size: None,
+            })
+            .collect();
+        let job = manager.add_job(
+            "test",
+            None,
+            tasks.clone(),
+            Arc::new(RecordingEventSink::default()),
+        );
+        let transfers = Transfers::default();
+        let (a, b, _) = tokio::join!(
+            manager.download(&job, &tasks[0], &transfers),
+            manager.download(&job, &tasks[1], &transfers),
+            async {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                assert_eq!(manager.connections.available_permits(), 0);
+                manager.set_connection_limit(1);
+            }
+        );
+        assert!(a.unwrap() && b.unwrap());
+        assert_eq!(manager.connection_limit(), 1);
+        assert_eq!(manager.connections.available_permits(), 1);
+        manager.set_connection_limit(3);
+        assert_eq!(manager.connections.available_permits(), 3);
+    }
+
+    /// Add a job to a manager and load the saved jobs into another one, like after a restart
+    fn restart_with_job(
+        dir: &std::path::Path,
+        instance_id: Option<Uuid>,
+    ) -> (DownloadManager, Uuid) {
+        let path = dir.join("download_jobs.json");
+        let job = {
+            let manager = DownloadManager::load(path.clone());
+            let download = Download {
+                url: "https://example.com/client.jar".to_string(),
+                file: dir.join("client.jar"),
+                sha1: None,
+                size: None,
+            };
+            manager
+                .add_job(
+                    "test",
+                    instance_id,
+                    vec![download],
+                    Arc::new(RecordingEventSink::default()),
+                )
+                .id()
+        };
+        (DownloadManager::load(path), job)
+    }
+
+    #[test]
+    fn unfinished_jobs_are_interrupted_after_restart() {
+        let dir = TempDir::new();
+        let instance_id = Some(Uuid::new_v4());
+        let (manager, job) = restart_with_job(&dir, instance_id);
+        let jobs = manager.list();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].id, job);
+        assert_eq!(jobs[0].state, JobState::Interrupted);
+        assert_eq!(jobs[0].instance_id, instance_id);
+        let saved = manager
+            .take_interrupted(job, &RecordingEventSink::default())
+            .unwrap();
+        // Resumed by installing the instance again
+        assert!(saved.downloads.is_empty());
+        assert!(manager.list().is_empty());
+        let files: Vec<_> = std::fs::read_dir(&*dir).unwrap().collect();
+        assert!(files.is_empty(), "no temp file is left");
+    }
+
+    #[test]
+    fn downloads_of_other_jobs_are_saved() {
+        let dir = TempDir::new();
+        let (manager, job) = restart_with_job(&dir, None);
+        let saved = manager
+            .take_interrupted(job, &RecordingEventSink::default())
+            .unwrap();
+        assert_eq!(saved.downloads.len(), 1);
+    }
+}
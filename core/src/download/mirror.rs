@@ -0,0 +1,238 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Rewrite the official urls to mirrors, and fall back to the next one on failure
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri_plugin_http::reqwest::{self, StatusCode, Url};
+
+use crate::HTTP_CLIENT;
+
+const BMCLAPI: &str = "https://bmclapi2.bangbang93.com";
+
+/// Official url prefixes and the path of them on BMCLAPI
+const BMCLAPI_RULES: &[(&str, &str)] = &[
+    ("https://piston-meta.mojang.com/", "/"),
+    ("https://piston-data.mojang.com/", "/"),
+    ("https://launchermeta.mojang.com/", "/"),
+    ("https://launcher.mojang.com/", "/"),
+    ("https://resources.download.minecraft.net/", "/assets/"),
+    ("https://libraries.minecraft.net/", "/maven/"),
+    ("https://maven.minecraftforge.net/", "/maven/"),
+    ("https://maven.neoforged.net/releases/", "/maven/"),
+    ("https://maven.fabricmc.net/", "/maven/"),
+    ("https://meta.fabricmc.net/", "/fabric-meta/"),
+];
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "prefix")]
+pub enum Mirror {
+    Official,
+    Bmclapi,
+    /// The official url is appended to the prefix, like `https://proxy.example.com/https://piston-data.mojang.com/...`
+    Custom(String),
+}
+
+impl Mirror {
+    /// `None` if the mirror doesn't have the file
+    pub fn rewrite(&self, url: &str) -> Option<String> {
+        match self {
+            Self::Official => Some(url.to_string()),
+            Self::Bmclapi => BMCLAPI_RULES.iter().find_map(|(official, path)| {
+                url.strip_prefix(official)
+                    .map(|rest| format!("{BMCLAPI}{path}{rest}"))
+            }),
+            Self::Custom(prefix) => Some(format!("{prefix}{url}")),
+        }
+    }
+}
+
+/// Third-party mirrors are never used unless they are added to the config, the metadata
+/// downloaded from them has no sha1 to check
+pub fn default_mirrors() -> Vec<Mirror> {
+    vec![Mirror::Official]
+}
+
+static MIRRORS: Lazy<RwLock<Vec<Mirror>>> = Lazy::new(|| RwLock::new(default_mirrors()));
+
+/// Called when the config is loaded or changed
+pub fn set_mirrors(mirrors: Vec<Mirror>) {
+    *MIRRORS.write().unwrap() = mirrors;
+}
+
+/// Urls to try in priority order, the original url is used if no mirror has the file
+pub fn candidates(url: &str, mirrors: &[Mirror]) -> Vec<String> {
+    let mut result: Vec<String> = vec![];
+    for candidate in mirrors.iter().filter_map(|mirror| mirror.rewrite(url)) {
+        if !result.contains(&candidate) {
+            result.push(candidate);
+        }
+    }
+    if result.is_empty() {
+        result.push(url.to_string());
+    }
+    result
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct HostStats {
+    pub successes: u64,
+    pub failures: u64,
+    /// Moving average of the time until the response headers are received
+    pub latency_ms: Option<u64>,
+}
+
+static STATS: Lazy<Mutex<HashMap<String, HostStats>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[tauri::command]
+pub fn get_mirror_stats() -> HashMap<String, HostStats> {
+    STATS.lock().unwrap().clone()
+}
+
+pub(super) fn host_of(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(url) => match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            _ => url.to_string(),
+        },
+        Err(_) => url.to_string(),
+    }
+}
+
+fn record(url: &str, latency: Option<Duration>) {
+    let mut stats = STATS.lock().unwrap();
+    let stats = stats.entry(host_of(url)).or_default();
+    match latency {
+        Some(latency) => {
+            let latency = latency.as_millis() as u64;
+            stats.successes += 1;
+            stats.latency_ms = Some(match stats.latency_ms {
+                Some(average) => (average * 3 + latency) / 4,
+                None => latency,
+            });
+        }
+        None => stats.failures += 1,
+    }
+}
+
+/// Send a GET request to the mirrors configured in [`DownloadConfig`](crate::config::download::DownloadConfig)
+pub async fn get(url: &str) -> anyhow::Result<reqwest::Response> {
+    get_range(url, 0).await
+}
+
+/// Ask for the bytes from `offset`, the response is `206 Partial Content` if the server supports it,
+/// or `416 Range Not Satisfiable` if `offset` is not before the end of the file
+pub async fn get_range(url: &str, offset: u64) -> anyhow::Result<reqwest::Response> {
+    let mirrors = MIRRORS.read().unwrap().clone();
+    get_from(url, &mirrors, offset).await
+}
+
+async fn get_from(url: &str, mirrors: &[Mirror], offset: u64) -> anyhow::Result<reqwest::Response> {
+    let mut last_error = anyhow!("No mirror for {url}");
+    for candidate in candidates(url, mirrors) {
+        let mut request = HTTP_CLIENT.get(&candidate);
+        if offset > 0 {
+            request = request.header("Range", format!("bytes={offset}-"));
+        }
+        let start = Instant::now();
+        match request.send().await {
+            // The part file is complete or broken, no other mirror can help with that
+            Ok(response)
+                if response.status().is_success()
+                    || (offset > 0 && response.status() == StatusCode::RANGE_NOT_SATISFIABLE) =>
+            {
+                record(&candidate, Some(start.elapsed()));
+                debug!("Downloading {url} from {candidate}");
+                return Ok(response);
+            }
+            Ok(response) => last_error = anyhow!("{candidate} returned {}", response.status()),
+            Err(err) => last_error = err.into(),
+        }
+        record(&candidate, None);
+        warn!("Failed to download from {candidate}: {last_error}");
+    }
+    Err(last_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::mock_server::{MockServer, Response};
+
+    const CLIENT_JAR: &str = "https://piston-data.mojang.com/v1/objects/abc/client.jar";
+
+    #[test]
+    fn rewrite_to_bmclapi() {
+        assert_eq!(
+            Mirror::Bmclapi.rewrite(CLIENT_JAR).unwrap(),
+            "https://bmclapi2.bangbang93.com/v1/objects/abc/client.jar"
+        );
+        assert_eq!(
+            Mirror::Bmclapi
+                .rewrite("https://resources.download.minecraft.net/ab/abcd")
+                .unwrap(),
+            "https://bmclapi2.bangbang93.com/assets/ab/abcd"
+        );
+        assert_eq!(
+            Mirror::Bmclapi
+                .rewrite("https://maven.neoforged.net/releases/net/neoforged/neoforge/x.jar")
+                .unwrap(),
+            "https://bmclapi2.bangbang93.com/maven/net/neoforged/neoforge/x.jar"
+        );
+        assert_eq!(Mirror::Bmclapi.rewrite("https://example.com/file"), None);
+    }
+
+    #[test]
+    fn candidates_in_priority_order() {
+        let mirrors = [
+            Mirror::Custom("https://proxy.example.com/".to_string()),
+            Mirror::Bmclapi,
+            Mirror::Official,
+            Mirror::Official,
+        ];
+        assert_eq!(
+            candidates(CLIENT_JAR, &mirrors),
+            [
+                format!("https://proxy.example.com/{CLIENT_JAR}"),
+                "https://bmclapi2.bangbang93.com/v1/objects/abc/client.jar".to_string(),
+                CLIENT_JAR.to_string(),
+            ]
+        );
+        assert_eq!(
+            candidates("https://example.com/file", &[Mirror::Bmclapi]),
+            ["https://example.com/file"]
+        );
+    }
+
+    #[tokio::test]
+    async fn fall_back_to_the_next_mirror() {
+        let broken = MockServer::start(|_| Response::new(502, ""));
+        let working = MockServer::start(|_| Response::new(200, "jar"));
+        let mirrors = [
+            Mirror::Custom(format!("{}/", broken.url)),
+            Mirror::Custom(format!("{}/", working.url)),
+        ];
+        let response = get_from(CLIENT_JAR, &mirrors, 0).await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "jar");
+        assert_eq!(
+            working.requests(),
+            [("GET".to_string(), format!("/{CLIENT_JAR}"))]
+        );
+        let stats = get_mirror_stats();
+        assert_eq!(stats[&host_of(&broken.url)].failures, 1);
+        let working = &stats[&host_of(&working.url)];
+        assert_eq!((working.successes, working.failures), (1, 0));
+        assert!(working.latency_ms.is_some());
+    }
+}
@@ -0,0 +1,620 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+    collections::HashMap,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use futures::StreamExt;
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use serde::{Deserialize, Serialize, Serializer};
+use tauri_plugin_http::reqwest::StatusCode;
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+use crate::{
+    config::download::DownloadConfig,
+    event::{Event, SharedEventSink},
+};
+use limiter::{BandwidthLimiter, SystemClock};
+use manager::{JobState, DOWNLOADS};
+
+pub mod cache;
+pub mod limiter;
+pub mod manager;
+pub mod mirror;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Download {
+    pub url: String,
+    pub file: PathBuf,
+    pub sha1: Option<String>,
+    /// Checked after the download if there's no sha1
+    #[serde(default)]
+    pub size: Option<u64>,
+}
+
+/// Serialized as the step number used by the frontend
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InstallStep {
+    FetchVersionInfo = 1,
+    CheckFiles = 2,
+    DownloadFiles = 3,
+    InstallModLoader = 4,
+}
+
+impl Serialize for InstallStep {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*self as u8)
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct Progress {
+    pub completed: usize,
+    pub total: usize,
+    pub step: InstallStep,
+    /// Only the files with a known size are counted
+    pub completed_bytes: u64,
+    pub total_bytes: u64,
+    /// Seconds left at the average speed
+    pub eta: Option<u64>,
+    /// Files being downloaded
+    pub transfers: Vec<Transfer>,
+}
+
+impl Progress {
+    pub fn new(step: InstallStep) -> Self {
+        Self {
+            completed: 0,
+            total: 0,
+            step,
+            completed_bytes: 0,
+            total_bytes: 0,
+            eta: None,
+            transfers: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct Transfer {
+    pub url: String,
+    pub downloaded: u64,
+    pub size: Option<u64>,
+}
+
+fn sum_sizes(downloads: &[Download]) -> u64 {
+    downloads.iter().filter_map(|download| download.size).sum()
+}
+
+/// The bytes of the downloads in a job
+pub struct Transfers {
+    started: Instant,
+    total_bytes: u64,
+    /// Bytes since the last speed report
+    speed: AtomicUsize,
+    finished_bytes: AtomicU64,
+    running: Mutex<HashMap<PathBuf, Transfer>>,
+}
+
+impl Default for Transfers {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Transfers {
+    pub fn new(total_bytes: u64) -> Self {
+        Self {
+            started: Instant::now(),
+            total_bytes,
+            speed: AtomicUsize::new(0),
+            finished_bytes: AtomicU64::new(0),
+            running: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn start(&self, task: &Download, downloaded: u64) {
+        self.running.lock().unwrap().insert(
+            task.file.clone(),
+            Transfer {
+                url: task.url.clone(),
+                downloaded,
+                size: task.size,
+            },
+        );
+    }
+
+    fn advance(&self, task: &Download, bytes: usize) {
+        self.speed.fetch_add(bytes, Ordering::SeqCst);
+        if let Some(transfer) = self.running.lock().unwrap().get_mut(&task.file) {
+            transfer.downloaded += bytes as u64;
+        }
+    }
+
+    fn end(&self, task: &Download, completed: bool) {
+        self.running.lock().unwrap().remove(&task.file);
+        if completed {
+            self.finished_bytes
+                .fetch_add(task.size.unwrap_or(0), Ordering::SeqCst);
+        }
+    }
+
+    /// Bytes downloaded since the last call
+    pub fn take_speed(&self) -> usize {
+        self.speed.swap(0, Ordering::SeqCst)
+    }
+
+    pub fn progress(&self, completed: usize, total: usize) -> Progress {
+        let transfers: Vec<Transfer> = self.running.lock().unwrap().values().cloned().collect();
+        let running_bytes: u64 = transfers
+            .iter()
+            .filter_map(|transfer| Some(transfer.downloaded.min(transfer.size?)))
+            .sum();
+        let completed_bytes =
+            (self.finished_bytes.load(Ordering::SeqCst) + running_bytes).min(self.total_bytes);
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let eta = (completed_bytes > 0 && elapsed > 0.0).then(|| {
+            let speed = completed_bytes as f64 / elapsed;
+            ((self.total_bytes - completed_bytes) as f64 / speed).ceil() as u64
+        });
+        Progress {
+            completed,
+            total,
+            step: InstallStep::DownloadFiles,
+            completed_bytes,
+            total_bytes: self.total_bytes,
+            eta,
+            transfers,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct ProgressError {
+    pub step: InstallStep,
+}
+
+fn calculate_sha1_from_read<R: Read>(source: &mut R) -> String {
+    let mut hasher = sha1_smol::Sha1::new();
+    let mut buffer = [0; 1024];
+    loop {
+        let bytes_read = source.read(&mut buffer).unwrap();
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+    hasher.digest().to_string()
+}
+
+/// Apply the download config, called when the config is loaded or changed
+pub fn configure(config: &DownloadConfig) {
+    mirror::set_mirrors(config.mirrors.clone());
+    DOWNLOADS.set_connection_limit(config.max_connection);
+    LIMITER.set_limit(config.max_download_speed);
+    LIMITER.set_host_limits(&config.host_speed_limits);
+    cache::set_enabled(config.shared_cache);
+}
+
+static LIMITER: Lazy<BandwidthLimiter> = Lazy::new(|| BandwidthLimiter::new(Box::new(SystemClock)));
+
+/// Run the downloads as a job of [`DOWNLOADS`], returns an error if the job is cancelled or fails
+pub async fn download_files(
+    name: &str,
+    instance_id: Option<Uuid>,
+    downloads: Vec<Download>,
+    sink: SharedEventSink,
+    send_progress: bool,
+    send_error: bool,
+) -> anyhow::Result<()> {
+    let total = downloads.len();
+    let total_bytes = sum_sizes(&downloads);
+    if send_progress {
+        sink.progress(Progress {
+            total,
+            total_bytes,
+            ..Progress::new(InstallStep::CheckFiles)
+        });
+    }
+    let counter: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+    let checked_bytes: Arc<AtomicU64> = Arc::new(AtomicU64::new(0));
+    let check_files_finished: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+    let counter_sender_thread = {
+        let check_files_finished = check_files_finished.clone();
+        let counter = counter.clone();
+        let checked_bytes = checked_bytes.clone();
+        let sink = sink.clone();
+        thread::spawn(move || {
+            while !check_files_finished.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(500));
+                if send_progress {
+                    sink.progress(Progress {
+                        completed: counter.load(Ordering::SeqCst),
+                        total,
+                        completed_bytes: checked_bytes.load(Ordering::SeqCst),
+                        total_bytes,
+                        ..Progress::new(InstallStep::CheckFiles)
+                    });
+                }
+            }
+        })
+    };
+    let downloads: Vec<_> = downloads
+        .into_par_iter()
+        .filter(|download| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            checked_bytes.fetch_add(download.size.unwrap_or(0), Ordering::SeqCst);
+            if std::fs::metadata(&download.file).is_err() {
+                return true;
+            }
+            let mut file = match std::fs::File::open(&download.file) {
+                Ok(file) => file,
+                Err(_) => {
+                    return true;
+                }
+            };
+            if download.sha1.is_none() {
+                return true;
+            };
+            let file_hash = calculate_sha1_from_read(&mut file);
+            &file_hash != download.sha1.as_ref().unwrap()
+        })
+        .collect();
+    check_files_finished.store(true, Ordering::SeqCst);
+    counter_sender_thread.join().unwrap();
+    let downloads: Vec<_> = downloads
+        .into_par_iter()
+        .filter(|download| !cache::restore(download))
+        .collect();
+    let counter: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+    let total = downloads.len();
+    let total_bytes = sum_sizes(&downloads);
+    let transfers: Arc<Transfers> = Arc::new(Transfers::new(total_bytes));
+    let running_counter: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+    let (tx, rx) = mpsc::channel();
+    let (tx2, rx2) = mpsc::channel();
+    let running_counter_closure = {
+        let running_counter = running_counter.clone();
+        let counter = counter.clone();
+        let transfers = transfers.clone();
+        let sink = sink.clone();
+        move || {
+            let running_counter = running_counter;
+            for tick in 0.. {
+                let message = rx.try_recv();
+                if message == Ok("terminate") {
+                    break;
+                }
+                sink.emit(Event::RunningDownloadTasks(
+                    running_counter.load(Ordering::SeqCst),
+                ));
+                // The bytes of large files change between the finished files
+                if send_progress && tick % 5 == 0 {
+                    sink.progress(transfers.progress(counter.load(Ordering::SeqCst), total));
+                }
+                thread::sleep(Duration::from_millis(100))
+            }
+        }
+    };
+    let running_counter_thread = thread::spawn(running_counter_closure);
+    let speed_thread_closure = {
+        let transfers = transfers.clone();
+        let sink = sink.clone();
+        move || loop {
+            let message = rx2.try_recv();
+            if message == Ok("terminate") {
+                break;
+            }
+            thread::sleep(Duration::from_millis(2000));
+            sink.speed(transfers.take_speed());
+        }
+    };
+    let speed_thread = thread::spawn(speed_thread_closure);
+    let job = DOWNLOADS.add_job(name, instance_id, downloads.clone(), sink.clone());
+    sink.progress(transfers.progress(0, total));
+    futures::stream::iter(downloads)
+        .map(|task| {
+            let counter = counter.clone();
+            let transfers = transfers.clone();
+            let running_counter = running_counter.clone();
+            let sink = sink.clone();
+            let job = job.clone();
+            async move {
+                running_counter.fetch_add(1, Ordering::SeqCst);
+                match DOWNLOADS.download(&job, &task, &transfers).await {
+                    Ok(true) => {
+                        cache::insert(&task);
+                        counter.fetch_add(1, Ordering::SeqCst);
+                    }
+                    Ok(false) => (),
+                    Err(err) => {
+                        warn!("Failed to download {}: {err}", task.url);
+                        if send_error {
+                            sink.error(ProgressError {
+                                step: InstallStep::DownloadFiles,
+                            });
+                        }
+                    }
+                }
+            }
+        })
+        .buffer_unordered(DOWNLOADS.connection_limit())
+        .for_each_concurrent(None, |_| async {
+            let counter = counter.clone().load(Ordering::SeqCst);
+            running_counter.fetch_sub(1, Ordering::SeqCst);
+            if send_progress {
+                sink.progress(transfers.progress(counter, total));
+            }
+        })
+        .await;
+    tx.send("terminate").unwrap();
+    tx2.send("terminate").unwrap();
+    speed_thread.join().unwrap();
+    running_counter_thread.join().unwrap();
+    cache::save();
+    match DOWNLOADS.finish(&job) {
+        JobState::Completed => Ok(()),
+        state => Err(anyhow!("The download job {name} is {state:?}")),
+    }
+}
+
+/// Attempts of one file before its download fails
+pub const MAX_ATTEMPTS: usize = 5;
+
+/// Download one file, resuming after failures
+pub async fn download_single(task: &Download) -> anyhow::Result<()> {
+    let mut retried = 0;
+    loop {
+        match download_resumable(task, &Transfers::default(), &|| false).await {
+            Ok(_) => return Ok(()),
+            Err(err) => {
+                retried += 1;
+                warn!("Downloaded failed: {}, retried: {retried}, {err}", task.url);
+                if retried >= MAX_ATTEMPTS {
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
+
+/// `<file>.part`, kept between attempts so the download can be resumed
+pub fn part_path(file: &Path) -> PathBuf {
+    let mut part = file.as_os_str().to_owned();
+    part.push(".part");
+    PathBuf::from(part)
+}
+
+/// Download into the part file, continuing from its end if the server supports `Range`, and
+/// move it to the target after the sha1 check. Returns `false` if stopped by `stop`.
+pub async fn download_resumable(
+    task: &Download,
+    transfers: &Transfers,
+    stop: &(dyn Fn() -> bool + Sync),
+) -> anyhow::Result<bool> {
+    let result = download_to_part(task, transfers, stop).await;
+    transfers.end(task, matches!(result, Ok(true)));
+    result
+}
+
+async fn download_to_part(
+    task: &Download,
+    transfers: &Transfers,
+    stop: &(dyn Fn() -> bool + Sync),
+) -> anyhow::Result<bool> {
+    let parent = task.file.parent().ok_or(anyhow!("Invalid path"))?;
+    tokio::fs::create_dir_all(parent).await?;
+    let part_path = part_path(&task.file);
+    let mut downloaded = tokio::fs::metadata(&part_path)
+        .await
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+    if task.size.is_some_and(|size| downloaded >= size) {
+        // Complete but not moved, or broken, it will be checked again after downloading
+        tokio::fs::remove_file(&part_path).await?;
+        downloaded = 0;
+    }
+    // Kept on network errors, the next attempt continues from it
+    let mut response = mirror::get_range(&task.url, downloaded).await?;
+    if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        tokio::fs::remove_file(&part_path).await?;
+        return Err(anyhow!("{} returned {}", task.url, response.status()));
+    }
+    let mut file = if response.status() == StatusCode::PARTIAL_CONTENT {
+        debug!("Resuming {} from {downloaded} bytes", task.url);
+        transfers.start(task, downloaded);
+        tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&part_path)
+            .await?
+    } else {
+        // The server ignored the range, start over
+        transfers.start(task, 0);
+        tokio::fs::File::create(&part_path).await?
+    };
+    let host = mirror::host_of(response.url().as_str());
+    while let Some(chunk) = response.chunk().await? {
+        LIMITER.acquire(&host, chunk.len()).await;
+        if stop() {
+            file.flush().await?;
+            return Ok(false);
+        }
+        file.write_all(&chunk).await?;
+        transfers.advance(task, chunk.len());
+    }
+    file.sync_all().await?;
+    drop(file);
+    if let Some(sha1) = &task.sha1 {
+        let mut part = std::fs::File::open(&part_path)?;
+        if &calculate_sha1_from_read(&mut part) != sha1 {
+            tokio::fs::remove_file(&part_path).await?;
+            return Err(anyhow!("sha1 check failed for {}", task.url));
+        }
+    } else if let Some(size) = task.size {
+        if tokio::fs::metadata(&part_path).await?.len() != size {
+            tokio::fs::remove_file(&part_path).await?;
+            return Err(anyhow!("size check failed for {}", task.url));
+        }
+    }
+    tokio::fs::rename(&part_path, &task.file).await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{
+        mock_server::{MockServer, Request, Response},
+        temp_dir::TempDir,
+    };
+
+    const CONTENT: &str = "0123456789";
+
+    fn range_of(request: &Request) -> Option<usize> {
+        request
+            .headers
+            .iter()
+            .find(|(name, _)| name == "range")
+            .and_then(|(_, value)| {
+                value
+                    .strip_prefix("bytes=")?
+                    .strip_suffix('-')?
+                    .parse()
+                    .ok()
+            })
+    }
+
+    async fn download(
+        server: &MockServer,
+        file: &Path,
+        sha1: Option<String>,
+    ) -> anyhow::Result<bool> {
+        let task = Download {
+            url: format!("{}/client.jar", server.url),
+            file: file.to_path_buf(),
+            sha1,
+            size: Some(CONTENT.len() as u64),
+        };
+        download_resumable(&task, &Transfers::default(), &|| false).await
+    }
+
+    #[test]
+    fn count_the_bytes_of_transfers() {
+        let transfers = Transfers::new(100);
+        let task = |name: &str, size| Download {
+            url: format!("https://example.com/{name}"),
+            file: PathBuf::from(name),
+            sha1: None,
+            size,
+        };
+        let (jar, unknown) = (task("client.jar", Some(60)), task("unknown", None));
+        transfers.start(&jar, 10);
+        transfers.start(&unknown, 0);
+        transfers.advance(&jar, 20);
+        transfers.advance(&unknown, 1000);
+        let progress = transfers.progress(0, 2);
+        assert_eq!((progress.completed_bytes, progress.total_bytes), (30, 100));
+        assert_eq!(progress.transfers.len(), 2);
+        assert!(progress.eta.is_some());
+        assert_eq!(transfers.take_speed(), 1020);
+        assert_eq!(transfers.take_speed(), 0);
+        transfers.end(&jar, true);
+        transfers.end(&unknown, false);
+        let progress = transfers.progress(1, 2);
+        assert_eq!(progress.completed_bytes, 60);
+        assert!(progress.transfers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn resume_from_part_file() {
+        let ranges = Arc::new(Mutex::new(vec![]));
+        let server = {
+            let ranges = ranges.clone();
+            MockServer::start(move |request| {
+                ranges.lock().unwrap().push(range_of(request));
+                Response::new(206, &CONTENT[4..])
+            })
+        };
+        let dir = TempDir::new();
+        let file = dir.join("client.jar");
+        std::fs::write(part_path(&file), &CONTENT[..4]).unwrap();
+        let sha1 = sha1_smol::Sha1::from(CONTENT).digest().to_string();
+        assert!(download(&server, &file, Some(sha1)).await.unwrap());
+        assert_eq!(*ranges.lock().unwrap(), [Some(4)]);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), CONTENT);
+        assert!(!part_path(&file).exists());
+    }
+
+    #[tokio::test]
+    async fn keep_part_file_when_connection_fails() {
+        let ranges = Arc::new(Mutex::new(vec![]));
+        let server = {
+            let ranges = ranges.clone();
+            MockServer::start(move |request| {
+                let mut ranges = ranges.lock().unwrap();
+                ranges.push(range_of(request));
+                match ranges.len() {
+                    1 => Response::disconnect(),
+                    _ => Response::new(206, &CONTENT[4..]),
+                }
+            })
+        };
+        let dir = TempDir::new();
+        let file = dir.join("client.jar");
+        std::fs::write(part_path(&file), &CONTENT[..4]).unwrap();
+        assert!(download(&server, &file, None).await.is_err());
+        assert_eq!(
+            std::fs::read_to_string(part_path(&file)).unwrap(),
+            &CONTENT[..4]
+        );
+        assert!(download(&server, &file, None).await.unwrap());
+        assert_eq!(*ranges.lock().unwrap(), [Some(4), Some(4)]);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), CONTENT);
+    }
+
+    #[tokio::test]
+    async fn restart_when_range_is_not_satisfiable() {
+        let server = MockServer::start(|_| Response::new(416, ""));
+        let dir = TempDir::new();
+        let file = dir.join("client.jar");
+        std::fs::write(part_path(&file), "012345").unwrap();
+        assert!(download(&server, &file, None).await.is_err());
+        assert!(!part_path(&file).exists());
+    }
+
+    #[tokio::test]
+    async fn restart_when_range_is_ignored() {
+        let server = MockServer::start(|_| Response::new(200, CONTENT));
+        let dir = TempDir::new();
+        let file = dir.join("client.jar");
+        std::fs::write(part_path(&file), "broken").unwrap();
+        assert!(download(&server, &file, None).await.unwrap());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), CONTENT);
+    }
+
+    #[tokio::test]
+    async fn keep_nothing_when_sha1_mismatches() {
+        let server = MockServer::start(|_| Response::new(200, CONTENT));
+        let dir = TempDir::new();
+        let file = dir.join("client.jar");
+        assert!(download(&server, &file, Some("0".repeat(40)))
+            .await
+            .is_err());
+        assert!(!file.exists());
+        assert!(!part_path(&file).exists());
+    }
+}
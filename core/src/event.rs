@@ -0,0 +1,156 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Send the progress and events of the core logic somewhere.
+//!
+//! The GUI forwards them to the main window, the CLI prints them to the terminal,
+//! and the tests record them.
+
+use std::sync::{Arc, Mutex};
+
+use log::{debug, error};
+use serde::Serialize;
+use tauri::{Emitter, Manager};
+
+use crate::{
+    download::{Progress, ProgressError},
+    launch::{crash::CrashAnalysis, process::InstanceStatus, JavaVersionMismatch, Log},
+    APP_HANDLE,
+};
+
+/// The events without a dedicated method in [`EventSink`]
+#[derive(Clone)]
+pub enum Event {
+    RunningDownloadTasks(usize),
+    InstallSuccess,
+    AccountsChanged,
+    /// No account is selected, the GUI opens the account manager
+    AccountRequired,
+    JavaVersionMismatch(JavaVersionMismatch),
+    LaunchCrashed(CrashAnalysis),
+    /// A download job is added, or its state is changed
+    DownloadJobsChanged,
+}
+
+pub trait EventSink: Send + Sync {
+    fn progress(&self, progress: Progress);
+    /// Bytes downloaded in the last 2 seconds
+    fn speed(&self, speed: usize);
+    fn log(&self, log: Log);
+    fn error(&self, error: ProgressError);
+    fn state(&self, status: InstanceStatus);
+    fn emit(&self, event: Event);
+}
+
+pub type SharedEventSink = Arc<dyn EventSink>;
+
+/// Send everything to the main window
+pub struct TauriEventSink;
+
+impl TauriEventSink {
+    pub fn shared() -> SharedEventSink {
+        Arc::new(Self)
+    }
+}
+
+/// Events are dropped when the main window doesn't exist, e.g. it is closed
+fn emit_to_main_window<S: Serialize + Clone>(event: &str, payload: S) {
+    let Some(window) = APP_HANDLE.get().and_then(|app| app.get_window("main")) else {
+        debug!("No main window, {event} is dropped");
+        return;
+    };
+    if let Err(err) = window.emit(event, payload) {
+        error!("Failed to send {event} to the main window: {err}");
+    }
+}
+
+impl EventSink for TauriEventSink {
+    fn progress(&self, progress: Progress) {
+        emit_to_main_window("install_progress", progress);
+    }
+
+    fn speed(&self, speed: usize) {
+        emit_to_main_window("download_speed", speed);
+    }
+
+    fn log(&self, log: Log) {
+        emit_to_main_window("log", log);
+    }
+
+    fn error(&self, error: ProgressError) {
+        emit_to_main_window("install_error", error);
+    }
+
+    fn state(&self, status: InstanceStatus) {
+        emit_to_main_window("instance_state_changed", status);
+    }
+
+    fn emit(&self, event: Event) {
+        match event {
+            Event::RunningDownloadTasks(count) => {
+                emit_to_main_window("running_download_task", count)
+            }
+            Event::InstallSuccess => emit_to_main_window("install_success", ""),
+            Event::AccountsChanged => emit_to_main_window("refresh_accounts_list", ""),
+            Event::AccountRequired => emit_to_main_window("add-account", "add-account"),
+            Event::JavaVersionMismatch(mismatch) => {
+                emit_to_main_window("java_version_mismatch", mismatch)
+            }
+            Event::LaunchCrashed(analysis) => emit_to_main_window("launch_crashed", analysis),
+            Event::DownloadJobsChanged => emit_to_main_window("download_jobs_changed", ""),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum RecordedEvent {
+    Progress(Progress),
+    Speed(usize),
+    Log(Log),
+    Error(ProgressError),
+    State(InstanceStatus),
+    Event(Event),
+}
+
+/// Keep all events in memory, for tests
+#[derive(Default)]
+pub struct RecordingEventSink {
+    events: Mutex<Vec<RecordedEvent>>,
+}
+
+impl RecordingEventSink {
+    pub fn events(&self) -> Vec<RecordedEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    fn record(&self, event: RecordedEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+impl EventSink for RecordingEventSink {
+    fn progress(&self, progress: Progress) {
+        self.record(RecordedEvent::Progress(progress));
+    }
+
+    fn speed(&self, speed: usize) {
+        self.record(RecordedEvent::Speed(speed));
+    }
+
+    fn log(&self, log: Log) {
+        self.record(RecordedEvent::Log(log));
+    }
+
+    fn error(&self, error: ProgressError) {
+        self.record(RecordedEvent::Error(error));
+    }
+
+    fn state(&self, status: InstanceStatus) {
+        self.record(RecordedEvent::State(status));
+    }
+
+    fn emit(&self, event: Event) {
+        self.record(RecordedEvent::Event(event));
+    }
+}
@@ -9,7 +9,6 @@ use std::{
     fmt::Display,
     format,
     path::{Path, PathBuf},
-    str::FromStr,
 };
 
 use serde::{Deserialize, Serialize};
@@ -84,7 +83,7 @@ pub struct DataLocation {
     pub root: PathBuf,
     pub instances: PathBuf,
     pub cache: PathBuf,
-    pub default_jre: PathBuf,
+    pub runtimes: PathBuf,
     pub logs: PathBuf,
     pub resources: PathBuf,
     pub temp: PathBuf,
@@ -106,8 +105,7 @@ impl DataLocation {
                         .join(".cache/conic")
                 }
             },
-            // default_jre: data_folder.join("default_jre").join("bin").join("java"),
-            default_jre: PathBuf::from_str("/bin/java").unwrap(),
+            runtimes: data_folder_root.join("runtimes"),
             resources: data_folder_root.join("resources"),
             logs: data_folder_root.join("logs"),
             temp: temp_path,
@@ -119,6 +117,10 @@ impl DataLocation {
     pub fn get_instance_root(&self, instance_id: &Uuid) -> PathBuf {
         self.instances.join(instance_id.to_string())
     }
+
+    pub fn get_runtime_root(&self, component: &str) -> PathBuf {
+        self.runtimes.join(component)
+    }
 }
 
 impl Default for DataLocation {
@@ -2,9 +2,8 @@
 // Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crate::{folder::MinecraftLocation, version::Version};
+use crate::{download::mirror, folder::MinecraftLocation, version::Version};
 use log::info;
-use tauri_plugin_http::reqwest;
 
 /// Save fabric version.json
 ///
@@ -18,7 +17,7 @@ pub async fn install(
     let url = format!(
         "https://meta.fabricmc.net/v2/versions/loader/{mcversion}/{quilt_version}/profile/json"
     );
-    let response = reqwest::get(url).await.unwrap();
+    let response = mirror::get(&url).await.unwrap();
     let fabric_version_json: Version = response.json().await.unwrap();
     let version_name = fabric_version_json.id.clone();
     let json_path = minecraft.get_version_json(&version_name);
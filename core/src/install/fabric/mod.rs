@@ -4,7 +4,8 @@
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tauri_plugin_http::reqwest;
+
+use crate::download::mirror;
 
 pub mod install;
 pub use install::install;
@@ -49,7 +50,7 @@ pub struct LoaderArtifactList(Vec<FabricLoaderArtifact>);
 impl LoaderArtifactList {
     /// get loader artifacts
     pub async fn new(mcversion: &str) -> anyhow::Result<Self> {
-        Ok(reqwest::get(format!(
+        Ok(mirror::get(&format!(
             "https://meta.fabricmc.net/v2/versions/loader/{}",
             mcversion
         ))
@@ -2,19 +2,26 @@
 // Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
 // SPDX-License-Identifier: GPL-3.0-only
 
-use std::{io::BufRead, path::PathBuf, process::Stdio};
+use std::{
+    io::BufRead,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
 
 use log::{error, info, trace};
-use tokio::io::AsyncWriteExt;
-use uuid::Uuid;
 
-use crate::{platform::DELIMITER, DATA_LOCATION, HTTP_CLIENT};
+use crate::{
+    download::{download_single, Download},
+    platform::DELIMITER,
+    DATA_LOCATION,
+};
 
 /// Forge Install Bootstrapper - By bangbang93
 /// [Github Repo](https://github.com/bangbang93/forge-install-bootstrapper)
 const FORGE_INSTALL_BOOTSTRAPPER: &[u8] = include_bytes!("./forge-install-bootstrapper.jar");
 
 pub async fn install(
+    java: &Path,
     install_dir: &PathBuf,
     forge_version: &str,
     mcversion: &str,
@@ -39,8 +46,7 @@ pub async fn install(
     if let Some(bootstrapper) = bootstrapper {
         tokio::fs::write(&bootstrapper_path, bootstrapper).await?;
     }
-    let java = DATA_LOCATION.default_jre.clone();
-    info!("Running installer");
+    info!("Running installer with {}", java.display());
     let mut command = match bootstrapper {
         Some(_) => std::process::Command::new(java)
             .arg("-cp")
@@ -96,19 +102,16 @@ pub async fn install(
 async fn download_installer(mcversion: &str, forge_version: &str) -> anyhow::Result<PathBuf> {
     let installer_url  = format!("https://maven.minecraftforge.net/net/minecraftforge/forge/{mcversion}-{forge_version}/forge-{mcversion}-{forge_version}-installer.jar");
     info!("The installer url is: {installer_url}");
-    let installer_path = DATA_LOCATION.temp.join(format!("{}.jar", Uuid::new_v4()));
-    tokio::fs::create_dir_all(
-        installer_path
-            .parent()
-            .ok_or(anyhow::Error::msg("Unknown Error"))?,
-    )
+    // Not random, so an interrupted download can be resumed next time
+    let installer_path = DATA_LOCATION
+        .temp
+        .join(format!("forge-{mcversion}-{forge_version}-installer.jar"));
+    download_single(&Download {
+        url: installer_url,
+        file: installer_path.clone(),
+        sha1: None,
+        size: None,
+    })
     .await?;
-    let mut file = tokio::fs::File::create(&installer_path).await?;
-    let response = HTTP_CLIENT.get(installer_url).send().await?;
-    if !response.status().is_success() {
-        return Err(anyhow::Error::msg("Forge website return error"));
-    }
-    let src = response.bytes().await?;
-    file.write_all(&src).await?;
     Ok(installer_path)
 }
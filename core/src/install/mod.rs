@@ -2,21 +2,24 @@
 // Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
 // SPDX-License-Identifier: GPL-3.0-only
 
+use std::path::PathBuf;
+
 use forge::version_list::ForgeVersionList;
 use log::{debug, error, info};
 use neoforged::NeoforgedVersionList;
 use quilt::QuiltVersionList;
-use tauri::Emitter;
 use tokio::io::AsyncWriteExt;
 use vanilla::generate_download_info;
 
 use crate::{
     config::instance::{InstanceRuntime, ModLoaderType},
-    download::{download_files, Progress, ProgressError},
+    download::{download_files, InstallStep, Progress, ProgressError},
+    event::{Event, SharedEventSink, TauriEventSink},
     folder::MinecraftLocation,
     instance::Instance,
-    version::VersionManifest,
-    Storage, DATA_LOCATION, MAIN_WINDOW,
+    java::runtime::ensure_java_runtime,
+    version::{Version, VersionManifest},
+    DATA_LOCATION,
 };
 
 mod fabric;
@@ -68,20 +71,16 @@ pub async fn get_neoforged_version_list(mcversion: String) -> Option<Vec<String>
 }
 
 #[tauri::command(async)]
-pub async fn install(
-    storage: tauri::State<'_, Storage>,
+pub async fn install(instance: Instance) -> std::result::Result<(), ()> {
+    install_instance(instance, TauriEventSink::shared()).await
+}
+
+/// Install the game and the mod loader of the instance
+pub async fn install_instance(
     instance: Instance,
+    sink: SharedEventSink,
 ) -> std::result::Result<(), ()> {
-    MAIN_WINDOW
-        .emit(
-            "install_progress",
-            Progress {
-                completed: 0,
-                total: 0,
-                step: 1, // Step 1 is get version info
-            },
-        )
-        .unwrap();
+    sink.progress(Progress::new(InstallStep::FetchVersionInfo));
     info!(
         "Start installing the game for instance {}",
         instance.config.name
@@ -106,41 +105,36 @@ pub async fn install(
     {
         Ok(x) => x,
         Err(_) => {
-            MAIN_WINDOW
-                .emit("install_error", ProgressError { step: 1 })
-                .unwrap();
+            sink.error(ProgressError {
+                step: InstallStep::FetchVersionInfo,
+            });
             return Err(());
         }
     };
     info!("Start downloading file");
-    let config = storage.config.lock().unwrap().clone();
-    download_files(
+    if let Err(err) = download_files(
+        &instance.config.name,
+        Some(instance.id),
         download_list,
+        sink.clone(),
         true,
         true,
-        config.download.max_connection,
-        config.download.max_download_speed,
     )
-    .await;
+    .await
+    {
+        error!("{err}");
+        return Err(());
+    }
     if runtime.mod_loader_type.is_some() {
         info!("Install mod loader");
-        MAIN_WINDOW
-            .emit(
-                "install_progress",
-                Progress {
-                    completed: 0,
-                    total: 0,
-                    step: 4,
-                },
-            )
-            .unwrap();
-        match install_mod_loader(runtime).await {
+        sink.progress(Progress::new(InstallStep::InstallModLoader));
+        match install_mod_loader(runtime, sink.clone()).await {
             Ok(_) => (),
             Err(_) => {
                 error!("Failed to install mod loader");
-                MAIN_WINDOW
-                    .emit("install_error", ProgressError { step: 4 })
-                    .unwrap();
+                sink.error(ProgressError {
+                    step: InstallStep::InstallModLoader,
+                });
                 return Err(());
             }
         };
@@ -154,11 +148,11 @@ pub async fn install(
     .await
     .unwrap();
     lock_file.write_all(b"ok").await.unwrap();
-    MAIN_WINDOW.emit("install_success", "").unwrap();
+    sink.emit(Event::InstallSuccess);
     Ok(())
 }
 
-async fn install_mod_loader(runtime: InstanceRuntime) -> anyhow::Result<()> {
+async fn install_mod_loader(runtime: InstanceRuntime, sink: SharedEventSink) -> anyhow::Result<()> {
     let mod_loader_type = runtime.mod_loader_type.unwrap();
     let mod_loader_version = runtime
         .mod_loader_version
@@ -181,12 +175,29 @@ async fn install_mod_loader(runtime: InstanceRuntime) -> anyhow::Result<()> {
             .await?
         }
         ModLoaderType::Forge => {
-            forge::install(&DATA_LOCATION.root, &mod_loader_version, &runtime.minecraft).await?
+            let java = prepare_installer_java(&runtime.minecraft, sink).await?;
+            forge::install(
+                &java,
+                &DATA_LOCATION.root,
+                &mod_loader_version,
+                &runtime.minecraft,
+            )
+            .await?
         }
         ModLoaderType::Neoforged => {
-            neoforged::install(&DATA_LOCATION.root, &mod_loader_version).await?
+            let java = prepare_installer_java(&runtime.minecraft, sink).await?;
+            neoforged::install(&java, &DATA_LOCATION.root, &mod_loader_version).await?
         }
     }
 
     anyhow::Ok(())
 }
+
+/// Get the java required by the vanilla version to run the mod loader installer
+async fn prepare_installer_java(mcversion: &str, sink: SharedEventSink) -> anyhow::Result<PathBuf> {
+    let minecraft_location = MinecraftLocation::new(&DATA_LOCATION.root);
+    let version = Version::from_versions_folder(&minecraft_location, mcversion)?
+        .parse(&minecraft_location, &[])
+        .await?;
+    ensure_java_runtime(&version.java_version, sink).await
+}
@@ -2,14 +2,19 @@
 // Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
 // SPDX-License-Identifier: GPL-3.0-only
 
-use std::{io::BufRead, path::PathBuf, process::Stdio};
+use std::{
+    io::BufRead,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
 
 use log::{error, info, trace};
 use serde::{Deserialize, Serialize};
-use tokio::io::AsyncWriteExt;
-use uuid::Uuid;
 
-use crate::{DATA_LOCATION, HTTP_CLIENT};
+use crate::{
+    download::{download_single, mirror, Download},
+    DATA_LOCATION,
+};
 
 #[derive(Deserialize, Serialize, Clone)]
 pub struct NeoforgedVersionList {
@@ -20,20 +25,23 @@ pub struct NeoforgedVersionList {
 
 impl NeoforgedVersionList {
     pub async fn new() -> anyhow::Result<Self> {
-        Ok(HTTP_CLIENT
-            .get("https://maven.neoforged.net/api/maven/versions/releases/net/neoforged/neoforge")
-            .send()
-            .await?
-            .json()
-            .await?)
+        Ok(mirror::get(
+            "https://maven.neoforged.net/api/maven/versions/releases/net/neoforged/neoforge",
+        )
+        .await?
+        .json()
+        .await?)
     }
 }
 
-pub async fn install(install_dir: &PathBuf, neoforged_version: &str) -> anyhow::Result<()> {
+pub async fn install(
+    java: &Path,
+    install_dir: &PathBuf,
+    neoforged_version: &str,
+) -> anyhow::Result<()> {
     info!("Start downloading the neoforged installer");
     let installer_path = download_installer(neoforged_version).await?;
-    let java = DATA_LOCATION.default_jre.clone();
-    info!("Running installer");
+    info!("Running installer with {}", java.display());
     let mut command = std::process::Command::new(java)
         .arg("-jar")
         .arg(&installer_path)
@@ -75,19 +83,16 @@ pub async fn install(install_dir: &PathBuf, neoforged_version: &str) -> anyhow::
 async fn download_installer(neoforged_version: &str) -> anyhow::Result<PathBuf> {
     let installer_url  = format!("https://maven.neoforged.net/releases/net/neoforged/neoforge/{neoforged_version}/neoforge-{neoforged_version}-installer.jar");
     info!("The installer url is: {installer_url}");
-    let installer_path = DATA_LOCATION.temp.join(format!("{}.jar", Uuid::new_v4()));
-    tokio::fs::create_dir_all(
-        installer_path
-            .parent()
-            .ok_or(anyhow::Error::msg("Unknown Error"))?,
-    )
+    // Not random, so an interrupted download can be resumed next time
+    let installer_path = DATA_LOCATION
+        .temp
+        .join(format!("neoforge-{neoforged_version}-installer.jar"));
+    download_single(&Download {
+        url: installer_url,
+        file: installer_path.clone(),
+        sha1: None,
+        size: None,
+    })
     .await?;
-    let mut file = tokio::fs::File::create(&installer_path).await?;
-    let response = HTTP_CLIENT.get(installer_url).send().await?;
-    if !response.status().is_success() {
-        return Err(anyhow::Error::msg("Neoforged website return error"));
-    };
-    let src = response.bytes().await?;
-    file.write_all(&src).await?;
     Ok(installer_path)
 }
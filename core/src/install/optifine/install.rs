@@ -37,6 +37,7 @@ where
         url,
         file: dest_path,
         sha1: None,
+        size: None,
     })
     .await?;
 
@@ -6,12 +6,10 @@ use std::str::FromStr;
 
 use anyhow::{anyhow, Result};
 use serde_json::Value;
-use tauri_plugin_http::reqwest;
 use tokio::io::AsyncWriteExt;
 
-use crate::download::Download;
+use crate::download::{mirror, Download};
 use crate::version::ResolvedLibrary;
-use crate::HTTP_CLIENT;
 use crate::{
     folder::MinecraftLocation,
     version::{self, AssetIndex, AssetIndexObject, ResolvedVersion, VersionManifest},
@@ -30,6 +28,7 @@ pub(crate) fn generate_libraries_downloads(
                 .libraries
                 .join(library.download_info.path),
             sha1: library.download_info.sha1,
+            size: library.download_info.size,
         })
         .collect()
 }
@@ -38,12 +37,26 @@ pub async fn generate_assets_downloads(
     asset_index: AssetIndex,
     minecraft_location: &MinecraftLocation,
 ) -> Result<Vec<Download>> {
-    let asset_index_url = reqwest::Url::parse(asset_index.url.as_ref())?;
-    let asset_index_raw = reqwest::get(asset_index_url).await?.text().await?;
-    let asset_index_json: Value = serde_json::from_str(asset_index_raw.as_ref())?;
+    let asset_index_raw = mirror::get(&asset_index.url).await?.text().await?;
+    let mut assets = generate_asset_objects_downloads(&asset_index_raw, minecraft_location)?;
+    assets.push(Download {
+        url: asset_index.url,
+        file: minecraft_location.get_assets_index(&asset_index.id),
+        sha1: asset_index.sha1,
+        size: Some(asset_index.size),
+    });
+    Ok(assets)
+}
+
+/// The objects listed in the content of an asset index
+pub(crate) fn generate_asset_objects_downloads(
+    asset_index_raw: &str,
+    minecraft_location: &MinecraftLocation,
+) -> Result<Vec<Download>> {
+    let asset_index_json: Value = serde_json::from_str(asset_index_raw)?;
     let asset_index_object: AssetIndexObject =
         serde_json::from_value(asset_index_json["objects"].clone())?;
-    let mut assets: Vec<_> = asset_index_object
+    Ok(asset_index_object
         .into_iter()
         .map(|obj| Download {
             url: format!(
@@ -57,17 +70,12 @@ pub async fn generate_assets_downloads(
                 .join(&obj.1.hash[0..2])
                 .join(&obj.1.hash),
             sha1: Some(obj.1.hash),
+            size: Some(obj.1.size as u64),
         })
-        .collect();
-    assets.push(Download {
-        url: asset_index.url,
-        file: minecraft_location.get_assets_index(&asset_index.id),
-        sha1: None,
-    });
-    Ok(assets)
+        .collect())
 }
 
-const LOF4J2_CONFIGURATION: &[u8] = include_bytes!("./log4j2.xml");
+pub(crate) const LOF4J2_CONFIGURATION: &[u8] = include_bytes!("./log4j2.xml");
 
 /// Save the log4j2 configuration file
 pub async fn generate_log4j2_configuration_file(
@@ -96,12 +104,7 @@ pub async fn generate_download_info(
         return Err(anyhow!("Bad version manifest"));
     };
     let version_metadata = version_metadata.first().unwrap();
-    let version_json_raw = HTTP_CLIENT
-        .get(version_metadata.url.clone())
-        .send()
-        .await?
-        .text()
-        .await?;
+    let version_json_raw = mirror::get(&version_metadata.url).await?.text().await?;
     let version = version::Version::from_str(&version_json_raw)?
         .parse(&minecraft_location, &[])
         .await?;
@@ -122,6 +125,7 @@ pub async fn generate_download_info(
         ),
         file: minecraft_location.versions.join(format!("{id}/{id}.jar")),
         sha1: Some(client.sha1.to_string()),
+        size: Some(client.size),
     });
     download_list.extend(generate_libraries_downloads(
         &version.libraries,
@@ -0,0 +1,239 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Find the Java installations on this computer
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{anyhow, Result};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+
+use crate::{platform::OsFamily, version::JavaVersion, DATA_LOCATION, PLATFORM_INFO};
+
+use super::runtime::get_java_executable;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct JavaInstallation {
+    pub path: PathBuf,
+    pub version: String,
+    pub major_version: i32,
+    pub vendor: String,
+    pub arch: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub enum JavaCompatibility {
+    Compatible,
+    /// The java is newer than the version required, some old mod loaders will crash
+    TooNew,
+    /// The java is older than the version required, the game can't run
+    TooOld,
+}
+
+impl JavaInstallation {
+    /// Run `java -XshowSettings:properties -version` and read the properties
+    pub fn from_executable<P: AsRef<Path>>(java: P) -> Result<Self> {
+        let java = java.as_ref();
+        let output = Command::new(java)
+            .arg("-XshowSettings:properties")
+            .arg("-version")
+            .output()?;
+        // The properties are printed to stderr
+        let properties = parse_properties(&String::from_utf8_lossy(&output.stderr));
+        let version = properties
+            .get("java.version")
+            .ok_or(anyhow!("No java.version found in {}", java.display()))?
+            .to_string();
+        Ok(Self {
+            path: java.to_path_buf(),
+            major_version: parse_major_version(&version)?,
+            version,
+            vendor: properties.get("java.vendor").cloned().unwrap_or_default(),
+            arch: properties.get("os.arch").cloned().unwrap_or_default(),
+        })
+    }
+
+    pub fn check_compatibility(&self, required: &JavaVersion) -> JavaCompatibility {
+        match self.major_version.cmp(&required.major_version) {
+            std::cmp::Ordering::Less => JavaCompatibility::TooOld,
+            std::cmp::Ordering::Equal => JavaCompatibility::Compatible,
+            std::cmp::Ordering::Greater => JavaCompatibility::TooNew,
+        }
+    }
+}
+
+fn parse_properties(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once(" = "))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// `1.8.0_392` -> 8, `17.0.2` -> 17, `21` -> 21
+fn parse_major_version(version: &str) -> Result<i32> {
+    let mut split = version.split(['.', '_', '-', '+']);
+    let first = split.next().ok_or(anyhow!("Bad java version"))?;
+    if first == "1" {
+        Ok(split.next().ok_or(anyhow!("Bad java version"))?.parse()?)
+    } else {
+        Ok(first.parse()?)
+    }
+}
+
+fn java_executable_name() -> &'static str {
+    match PLATFORM_INFO.os_family {
+        OsFamily::Windows => "java.exe",
+        _ => "java",
+    }
+}
+
+/// Get the `bin/java` of every sub folder
+fn scan_java_homes<P: AsRef<Path>>(folder: P, java_home_suffix: &[&str]) -> Vec<PathBuf> {
+    let entries = match std::fs::read_dir(folder) {
+        Ok(x) => x,
+        Err(_) => return vec![],
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let mut path = entry.path();
+            for component in java_home_suffix {
+                path.push(component);
+            }
+            path.join("bin").join(java_executable_name())
+        })
+        .collect()
+}
+
+fn get_candidates() -> Vec<PathBuf> {
+    let mut candidates = vec![];
+    if let Ok(java_home) = std::env::var("JAVA_HOME") {
+        candidates.push(
+            PathBuf::from(java_home)
+                .join("bin")
+                .join(java_executable_name()),
+        );
+    }
+    if let Some(paths) = std::env::var_os("PATH") {
+        candidates.extend(std::env::split_paths(&paths).map(|x| x.join(java_executable_name())));
+    }
+    match PLATFORM_INFO.os_family {
+        OsFamily::Linux => {
+            candidates.extend(scan_java_homes("/usr/lib/jvm", &[]));
+            candidates.extend(scan_java_homes("/usr/lib64/jvm", &[]));
+        }
+        OsFamily::Macos => {
+            candidates.extend(scan_java_homes(
+                "/Library/Java/JavaVirtualMachines",
+                &["Contents", "Home"],
+            ));
+        }
+        OsFamily::Windows => {
+            for program_files in ["ProgramFiles", "ProgramFiles(x86)"] {
+                if let Ok(folder) = std::env::var(program_files) {
+                    let folder = PathBuf::from(folder);
+                    candidates.extend(scan_java_homes(folder.join("Java"), &[]));
+                    candidates.extend(scan_java_homes(folder.join("Eclipse Adoptium"), &[]));
+                    candidates.extend(scan_java_homes(folder.join("Zulu"), &[]));
+                }
+            }
+        }
+    }
+    if let Ok(home) = std::env::var(match PLATFORM_INFO.os_family {
+        OsFamily::Windows => "USERPROFILE",
+        _ => "HOME",
+    }) {
+        candidates.extend(scan_java_homes(
+            PathBuf::from(home).join(".sdkman/candidates/java"),
+            &[],
+        ));
+    }
+    if let Ok(entries) = std::fs::read_dir(&DATA_LOCATION.runtimes) {
+        candidates.extend(
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| get_java_executable(entry.path())),
+        );
+    }
+    candidates
+}
+
+/// Find all java installations and read their version
+pub fn find_java_installations() -> Vec<JavaInstallation> {
+    let mut visited = HashSet::new();
+    let mut result = vec![];
+    for candidate in get_candidates() {
+        if !candidate.is_file() {
+            continue;
+        }
+        // Skip the symlinks point to the same java, such as `/usr/bin/java`
+        let real_path = std::fs::canonicalize(&candidate).unwrap_or(candidate.clone());
+        if !visited.insert(real_path) {
+            continue;
+        }
+        match JavaInstallation::from_executable(&candidate) {
+            Ok(installation) => {
+                debug!(
+                    "Found Java {} at {}",
+                    installation.version,
+                    candidate.display()
+                );
+                result.push(installation)
+            }
+            Err(_) => debug!("Could not get java version from {}", candidate.display()),
+        }
+    }
+    info!("Found {} Java installations", result.len());
+    result
+}
+
+/// Every candidate runs `java -version`, so the scan happens on a blocking thread
+#[tauri::command(async)]
+pub async fn get_java_installations() -> Vec<JavaInstallation> {
+    tokio::task::spawn_blocking(find_java_installations)
+        .await
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROPERTIES: &str = r#"Property settings:
+    file.encoding = UTF-8
+    java.home = /usr/lib/jvm/java-17-openjdk
+    java.vendor = Eclipse Adoptium
+    java.version = 17.0.2
+    os.arch = amd64
+    user.dir = /home/steve
+
+openjdk version "17.0.2" 2022-01-18
+"#;
+
+    #[test]
+    fn major_version() {
+        assert_eq!(parse_major_version("1.8.0_402").unwrap(), 8);
+        assert_eq!(parse_major_version("17.0.2").unwrap(), 17);
+        assert_eq!(parse_major_version("21").unwrap(), 21);
+        assert_eq!(parse_major_version("21-ea").unwrap(), 21);
+        assert!(parse_major_version("").is_err());
+        assert!(parse_major_version("1").is_err());
+    }
+
+    #[test]
+    fn properties() {
+        let properties = parse_properties(PROPERTIES);
+        assert_eq!(properties["java.version"], "17.0.2");
+        assert_eq!(properties["java.vendor"], "Eclipse Adoptium");
+        assert_eq!(properties["os.arch"], "amd64");
+        assert_eq!(properties["java.home"], "/usr/lib/jvm/java-17-openjdk");
+        assert!(!properties.contains_key("openjdk version"));
+    }
+}
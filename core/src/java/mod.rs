@@ -0,0 +1,8 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Java runtime management
+
+pub mod discovery;
+pub mod runtime;
@@ -0,0 +1,391 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Install the Java runtime required by a Minecraft version from Mojang's runtime manifest
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    download::{download_files, mirror, Download},
+    event::SharedEventSink,
+    platform::OsFamily,
+    version::JavaVersion,
+    DATA_LOCATION, PLATFORM_INFO,
+};
+
+pub const DEFAULT_RUNTIME_MANIFEST_URL: &str = "https://launchermeta.mojang.com/v1/products/java-runtime/2ec0cc96c44e5a76b9c8b7c39df7210883d12871/all.json";
+
+const RUNTIME_LOCK_FILE: &str = ".conic-runtime-ok";
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RuntimeDownload {
+    pub sha1: String,
+    pub size: u64,
+    pub url: String,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RuntimeVersionInfo {
+    pub name: String,
+    pub released: String,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RuntimeManifestEntry {
+    pub manifest: RuntimeDownload,
+    pub version: RuntimeVersionInfo,
+}
+
+/// The `all.json` provided by Mojang, platform -> component -> entries
+pub type RuntimeManifest = HashMap<String, HashMap<String, Vec<RuntimeManifestEntry>>>;
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RuntimeFileDownloads {
+    pub raw: RuntimeDownload,
+    pub lzma: Option<RuntimeDownload>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RuntimeFile {
+    Directory,
+    File {
+        #[serde(default)]
+        executable: bool,
+        downloads: RuntimeFileDownloads,
+    },
+    Link {
+        target: String,
+    },
+}
+
+/// The manifest of a single runtime component, lists every file with its sha1
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RuntimeFiles {
+    pub files: HashMap<String, RuntimeFile>,
+}
+
+/// Get the platform name used in the runtime manifest
+fn runtime_platform() -> Option<&'static str> {
+    match (&PLATFORM_INFO.os_family, PLATFORM_INFO.arch.as_str()) {
+        (OsFamily::Linux, "x64") => Some("linux"),
+        (OsFamily::Linux, "x86") => Some("linux-i386"),
+        (OsFamily::Macos, "x64") => Some("mac-os"),
+        (OsFamily::Macos, "aarch64") => Some("mac-os-arm64"),
+        (OsFamily::Windows, "x64") => Some("windows-x64"),
+        (OsFamily::Windows, "x86") => Some("windows-x86"),
+        (OsFamily::Windows, "aarch64") => Some("windows-arm64"),
+        _ => None,
+    }
+}
+
+/// Get the path of the java executable in a runtime folder
+pub fn get_java_executable<P: AsRef<Path>>(runtime_root: P) -> PathBuf {
+    let runtime_root = runtime_root.as_ref();
+    match PLATFORM_INFO.os_family {
+        OsFamily::Windows => runtime_root.join("bin").join("java.exe"),
+        OsFamily::Macos => runtime_root
+            .join("jre.bundle")
+            .join("Contents")
+            .join("Home")
+            .join("bin")
+            .join("java"),
+        OsFamily::Linux => runtime_root.join("bin").join("java"),
+    }
+}
+
+/// Make sure the runtime required by the version is installed, and return the java executable.
+///
+/// The runtime will not be checked again once it's installed completely, delete the
+/// `.conic-runtime-ok` file in the runtime folder to force a re-check.
+pub async fn ensure_java_runtime(
+    java_version: &JavaVersion,
+    sink: SharedEventSink,
+) -> Result<PathBuf> {
+    let runtime_root = DATA_LOCATION.get_runtime_root(&java_version.component);
+    ensure_java_runtime_in(
+        java_version,
+        DEFAULT_RUNTIME_MANIFEST_URL,
+        &runtime_root,
+        sink,
+    )
+    .await
+}
+
+async fn ensure_java_runtime_in(
+    java_version: &JavaVersion,
+    manifest_url: &str,
+    runtime_root: &Path,
+    sink: SharedEventSink,
+) -> Result<PathBuf> {
+    let java = get_java_executable(runtime_root);
+    if runtime_root.join(RUNTIME_LOCK_FILE).exists() && java.exists() {
+        info!(
+            "Java runtime {} is already installed",
+            java_version.component
+        );
+        return Ok(java);
+    }
+    install_java_runtime(java_version, manifest_url, runtime_root, sink).await
+}
+
+/// Download and verify a runtime from the manifest, return the java executable.
+pub async fn install_java_runtime(
+    java_version: &JavaVersion,
+    manifest_url: &str,
+    runtime_root: &Path,
+    sink: SharedEventSink,
+) -> Result<PathBuf> {
+    info!(
+        "Installing Java runtime {} (Java {})",
+        java_version.component, java_version.major_version
+    );
+    let platform = runtime_platform().ok_or(anyhow!("Unsupported platform for Java runtime"))?;
+    let manifest: RuntimeManifest = mirror::get(manifest_url).await?.json().await?;
+    let entry = manifest
+        .get(platform)
+        .and_then(|components| components.get(&java_version.component))
+        .and_then(|entries| entries.first())
+        .ok_or(anyhow!(
+            "No runtime {} for platform {}",
+            java_version.component,
+            platform
+        ))?;
+    info!("Found Java runtime version {}", entry.version.name);
+    let raw_files = mirror::get(&entry.manifest.url).await?.bytes().await?;
+    if sha1_smol::Sha1::from(&raw_files).digest().to_string() != entry.manifest.sha1 {
+        return Err(anyhow!("sha1 check failed for runtime manifest"));
+    }
+    let runtime_files: RuntimeFiles = serde_json::from_slice(&raw_files)?;
+
+    let _ = tokio::fs::remove_file(runtime_root.join(RUNTIME_LOCK_FILE)).await;
+    tokio::fs::create_dir_all(runtime_root).await?;
+    let mut downloads = vec![];
+    let mut executables = vec![];
+    let mut links = vec![];
+    for (path, file) in runtime_files.files {
+        let file_path = runtime_root.join(&path);
+        match file {
+            RuntimeFile::Directory => tokio::fs::create_dir_all(&file_path).await?,
+            RuntimeFile::File {
+                executable,
+                downloads: file_downloads,
+            } => {
+                if executable {
+                    executables.push(file_path.clone());
+                }
+                downloads.push(Download {
+                    url: file_downloads.raw.url,
+                    file: file_path,
+                    sha1: Some(file_downloads.raw.sha1),
+                    size: Some(file_downloads.raw.size),
+                });
+            }
+            RuntimeFile::Link { target } => links.push((file_path, target)),
+        }
+    }
+    let name = format!("Java runtime {}", java_version.component);
+    if let Err(err) = download_files(&name, None, downloads.clone(), sink, false, false).await {
+        warn!("{err}");
+    }
+    if let Some(missing) = downloads.iter().find(|download| !download.file.exists()) {
+        return Err(anyhow!(
+            "Failed to download runtime file {}",
+            missing.file.display()
+        ));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        for executable in executables {
+            tokio::fs::set_permissions(&executable, std::fs::Permissions::from_mode(0o755)).await?;
+        }
+        for (link, target) in links {
+            let _ = tokio::fs::remove_file(&link).await;
+            if let Some(parent) = link.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::symlink(&target, &link).await?;
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = executables;
+        if !links.is_empty() {
+            log::warn!("Symbolic links in the runtime are ignored on this platform");
+        }
+    }
+
+    let java = get_java_executable(runtime_root);
+    if !java.exists() {
+        return Err(anyhow!("Could not find java in {}", runtime_root.display()));
+    }
+    tokio::fs::write(runtime_root.join(RUNTIME_LOCK_FILE), &entry.version.name).await?;
+    info!("Java runtime installed to {}", runtime_root.display());
+    Ok(java)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        event::RecordingEventSink,
+        utils::{
+            mock_server::{MockServer, Response},
+            temp_dir::TempDir,
+        },
+    };
+
+    const JAVA: &str = "java binary";
+    const LIBRARY: &str = "library";
+
+    fn sha1(content: &str) -> String {
+        sha1_smol::Sha1::from(content).digest().to_string()
+    }
+
+    fn java_version() -> JavaVersion {
+        JavaVersion {
+            component: "java-runtime-gamma".to_string(),
+            major_version: 17,
+        }
+    }
+
+    /// Relative path of the java executable on this platform
+    fn java_path() -> String {
+        get_java_executable("").to_string_lossy().replace('\\', "/")
+    }
+
+    fn runtime_files(base: &str, java_sha1: String) -> Value {
+        let file = |name: &str, sha1: String, size: usize, executable: bool| {
+            json!({
+                "type": "file",
+                "executable": executable,
+                "downloads": { "raw": { "sha1": sha1, "size": size, "url": format!("{base}/{name}") } },
+            })
+        };
+        let mut files = serde_json::Map::new();
+        files.insert("lib".to_string(), json!({ "type": "directory" }));
+        files.insert(
+            "lib/library".to_string(),
+            file("library", sha1(LIBRARY), LIBRARY.len(), false),
+        );
+        files.insert(java_path(), file("java", java_sha1, JAVA.len(), true));
+        json!({ "files": files })
+    }
+
+    /// Serves `all.json`, the file list of the runtime and the files in it
+    fn mock_runtime(
+        component: &'static str,
+        manifest_sha1: Option<String>,
+        java_sha1: String,
+    ) -> MockServer {
+        MockServer::start(move |request| {
+            let base = format!("http://{}", request.header("host").unwrap_or_default());
+            let files = runtime_files(&base, java_sha1.clone()).to_string();
+            match request.path.as_str() {
+                "/all.json" => {
+                    let entry = json!([{
+                        "manifest": {
+                            "sha1": manifest_sha1.clone().unwrap_or_else(|| sha1(&files)),
+                            "size": files.len(),
+                            "url": format!("{base}/manifest.json"),
+                        },
+                        "version": { "name": "17.0.8", "released": "2023-07-18T00:00:00+00:00" },
+                    }]);
+                    let mut components = serde_json::Map::new();
+                    components.insert(component.to_string(), entry);
+                    let mut manifest = serde_json::Map::new();
+                    manifest.insert(
+                        runtime_platform().unwrap().to_string(),
+                        Value::Object(components),
+                    );
+                    Response::json(200, Value::Object(manifest))
+                }
+                "/manifest.json" => Response::json(200, serde_json::from_str(&files).unwrap()),
+                "/java" => Response::new(200, JAVA),
+                "/library" => Response::new(200, LIBRARY),
+                _ => Response::new(404, ""),
+            }
+        })
+    }
+
+    async fn install(server: &MockServer, root: &Path) -> Result<PathBuf> {
+        ensure_java_runtime_in(
+            &java_version(),
+            &format!("{}/all.json", server.url),
+            root,
+            Arc::new(RecordingEventSink::default()),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn files_are_placed_and_checked_once() {
+        let server = mock_runtime("java-runtime-gamma", None, sha1(JAVA));
+        let root = TempDir::new();
+        let java = install(&server, &root).await.unwrap();
+        assert_eq!(java, get_java_executable(&*root));
+        assert_eq!(std::fs::read_to_string(&java).unwrap(), JAVA);
+        assert_eq!(
+            std::fs::read_to_string(root.join("lib").join("library")).unwrap(),
+            LIBRARY
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode();
+            assert_eq!(mode(&java) & 0o111, 0o111);
+            assert_eq!(mode(&root.join("lib").join("library")) & 0o111, 0);
+        }
+        assert_eq!(
+            std::fs::read_to_string(root.join(RUNTIME_LOCK_FILE)).unwrap(),
+            "17.0.8"
+        );
+
+        // Installed runtimes are not checked again, until the lock file is removed
+        let requests = server.requests().len();
+        install(&server, &root).await.unwrap();
+        assert_eq!(server.requests().len(), requests);
+        std::fs::remove_file(root.join(RUNTIME_LOCK_FILE)).unwrap();
+        install(&server, &root).await.unwrap();
+        assert!(server.requests().len() > requests);
+    }
+
+    #[tokio::test]
+    async fn manifest_sha1_mismatch() {
+        let server = mock_runtime("java-runtime-gamma", Some("0".repeat(40)), sha1(JAVA));
+        let root = TempDir::new();
+        let error = install(&server, &root).await.unwrap_err();
+        assert!(error.to_string().contains("sha1 check failed"));
+        assert!(!root.join(RUNTIME_LOCK_FILE).exists());
+    }
+
+    #[tokio::test]
+    async fn file_sha1_mismatch() {
+        let server = mock_runtime("java-runtime-gamma", None, "0".repeat(40));
+        let root = TempDir::new();
+        assert!(install(&server, &root).await.is_err());
+        assert!(!get_java_executable(&*root).exists());
+        assert!(!root.join(RUNTIME_LOCK_FILE).exists());
+    }
+
+    #[tokio::test]
+    async fn missing_component_for_the_platform() {
+        let server = mock_runtime("java-runtime-alpha", None, sha1(JAVA));
+        let root = TempDir::new();
+        let error = install(&server, &root).await.unwrap_err();
+        assert!(error.to_string().contains("No runtime java-runtime-gamma"));
+    }
+}
@@ -4,6 +4,7 @@
 
 use std::collections::{HashMap, HashSet};
 
+use log::warn;
 use regex::Regex;
 use zip::ZipArchive;
 
@@ -30,7 +31,7 @@ pub async fn generate_command_arguments(
     let mut command_arguments = Vec::new();
 
     command_arguments.push(format!(
-        "\"-Dminecraft.client.jar={version_jar}\"",
+        "-Dminecraft.client.jar={version_jar}",
         version_jar = minecraft_location
             .get_version_jar(&instance.config.runtime.minecraft, None)
             .to_string_lossy()
@@ -45,14 +46,7 @@ pub async fn generate_command_arguments(
         .unwrap();
     if PLATFORM_INFO.os_family == OsFamily::Macos {
         command_arguments.push("-Xdock:name=Minecraft".to_string());
-        command_arguments.push(format!(
-            "-Xdock:icon={game_icon}",
-            game_icon = if game_icon.contains(" ") {
-                format!("\"{}\"", game_icon)
-            } else {
-                game_icon
-            }
-        ));
+        command_arguments.push(format!("-Xdock:icon={game_icon}"));
     }
     if launch_options.min_memory > 0 {
         command_arguments.push(format!("-Xms{}M", launch_options.min_memory));
@@ -97,25 +91,24 @@ pub async fn generate_command_arguments(
         }
     }
     if PLATFORM_INFO.os_family == OsFamily::Macos {
-        command_arguments.push("XstartOnFirstThread".to_string());
+        command_arguments.push("-XstartOnFirstThread".to_string());
     }
     if PLATFORM_INFO.os_family == OsFamily::Windows {
         command_arguments.push("-XX:HeapDumpPath=MojangTricksIntelDriversForPerformance_javaw.exe_minecraft.exe.heapdump".to_string());
     }
-    // TODO: support yggdrasil
-    //         if let Some(ygg) = launch_options.yggdrasil_agent.clone() {
-    //             command_arguments.push(format!(
-    //                 "-javaagent:{jar}={server}",
-    //                 jar = ygg.jar.to_string_lossy(),
-    //                 server = ygg.server
-    //             ));
-    //             command_arguments.push("-Dauthlibinjector.side=client".to_string());
-    //             if let Some(prefetched) = ygg.prefetched {
-    //                 command_arguments.push(format!(
-    //                     "-Dauthlibinjector.yggdrasil.prefetched={prefetched}"
-    //                 ));
-    //             }
-    //         }
+    if let Some(ygg) = launch_options.yggdrasil_agent.clone() {
+        command_arguments.push(format!(
+            "-javaagent:{jar}={server}",
+            jar = ygg.jar.to_string_lossy(),
+            server = ygg.server
+        ));
+        command_arguments.push("-Dauthlibinjector.side=client".to_string());
+        if let Some(prefetched) = ygg.prefetched {
+            command_arguments.push(format!(
+                "-Dauthlibinjector.yggdrasil.prefetched={prefetched}"
+            ));
+        }
+    }
     let mut jvm_options: HashMap<&str, String> = HashMap::new();
     jvm_options.insert(
         "natives_directory",
@@ -146,19 +139,12 @@ pub async fn generate_command_arguments(
             .get_version_root(&version.id)
             .join("log4j2.xml");
         if tokio::fs::try_exists(&file_path).await.unwrap() {
-            jvm_arguments.push(format!(
-                "\"{}\"",
-                argument.replace("${path}", file_path.to_string_lossy().as_ref())
-            ));
+            jvm_arguments.push(argument.replace("${path}", file_path.to_string_lossy().as_ref()));
         }
     }
     jvm_arguments.extend(version.arguments.jvm);
-    command_arguments.push(launch_options.extra_jvm_args.clone());
-    command_arguments.extend(
-        jvm_arguments
-            .iter()
-            .map(|arg| format(arg, jvm_options.clone(), false)),
-    );
+    command_arguments.extend(split_arguments(&launch_options.extra_jvm_args));
+    command_arguments.extend(jvm_arguments.iter().map(|arg| format(arg, &jvm_options)));
     command_arguments.push(
         version
             .main_class
@@ -190,7 +176,7 @@ pub async fn generate_command_arguments(
     game_options.insert("auth_uuid", launch_options.game_profile.uuid.clone());
     game_options.insert("auth_access_token", launch_options.access_token.clone());
     game_options.insert("user_properties", launch_options.properties.clone());
-    game_options.insert("user_type", "msa".to_string());
+    game_options.insert("user_type", launch_options.user_type.clone());
     game_options.insert("resolution_width", launch_options.width.to_string());
     game_options.insert("resolution_height", launch_options.height.to_string());
     command_arguments.extend(
@@ -198,9 +184,9 @@ pub async fn generate_command_arguments(
             .arguments
             .game
             .iter()
-            .map(|arg| format(arg, game_options.clone(), true)),
+            .map(|arg| format(arg, &game_options)),
     );
-    command_arguments.push(launch_options.extra_mc_args.clone());
+    command_arguments.extend(split_arguments(&launch_options.extra_mc_args));
     if let Some(server) = launch_options.server.clone() {
         command_arguments.extend(vec!["--server".to_string(), server.ip]);
         if let Some(port) = server.port {
@@ -281,18 +267,27 @@ fn resolve_classpath(
     classpath.join(DELIMITER)
 }
 
-fn format(template: &str, args: HashMap<&str, String>, is_game_option: bool) -> String {
+fn format(template: &str, args: &HashMap<&str, String>) -> String {
     let regex = Regex::new(r"\$\{(.*?)}").unwrap();
 
     regex
         .replace_all(template, |caps: &regex::Captures| {
             let key = String::from(&caps[1]);
-            let value = args.get(&caps[1]).unwrap_or(&key);
-            if value.contains(" ") && is_game_option {
-                format!("\"{}\"", value)
-            } else {
-                value.to_string()
-            }
+            args.get(&caps[1]).unwrap_or(&key).to_string()
         })
         .to_string()
 }
+
+/// Split the user custom arguments like a shell does
+fn split_arguments(arguments: &str) -> Vec<String> {
+    match shell_words::split(arguments) {
+        Ok(x) => x,
+        Err(_) => {
+            warn!("Could not parse arguments `{arguments}`, ignoring quotes");
+            arguments
+                .split_whitespace()
+                .map(|x| x.to_string())
+                .collect()
+        }
+    }
+}
@@ -2,45 +2,254 @@
 // Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
 // SPDX-License-Identifier: GPL-3.0-only
 
-use std::io::Read;
+use std::{
+    collections::HashSet,
+    io::Read,
+    path::{Path, PathBuf},
+};
 
 use anyhow::anyhow;
-use log::{info, warn};
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use tokio::io::AsyncWriteExt;
+use log::{error, info, warn};
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use serde::Serialize;
+use serde_json::Value;
 
 use crate::{
-    download::Download,
+    download::{download_files, download_resumable, mirror, Download, Transfers},
+    event::{SharedEventSink, TauriEventSink},
     folder::MinecraftLocation,
-    install::vanilla::{generate_assets_downloads, generate_libraries_downloads},
+    install::vanilla::{
+        generate_asset_objects_downloads, generate_assets_downloads, generate_libraries_downloads,
+        generate_log4j2_configuration_file, LOF4J2_CONFIGURATION,
+    },
     instance::Instance,
-    version::Version,
-    DATA_LOCATION, HTTP_CLIENT,
+    version::{AssetIndex, Version},
+    DATA_LOCATION,
 };
 
+const ASSETS_LOCK_FILE: &str = ".conic-assets-ok";
+pub(super) const LIBRARIES_LOCK_FILE: &str = ".conic-libraries-ok";
+
+/// The sha1 of the version json and the ones it inherits from, saved in the lock files so
+/// they are invalid once the version changes
+fn version_fingerprint(minecraft_location: &MinecraftLocation, version_id: &str) -> String {
+    let mut hasher = sha1_smol::Sha1::new();
+    let mut visited = HashSet::new();
+    let mut version_id = Some(version_id.to_string());
+    while let Some(id) = version_id {
+        // A broken version json may inherit from itself
+        if !visited.insert(id.clone()) {
+            break;
+        }
+        let Ok(raw) = std::fs::read(minecraft_location.get_version_json(&id)) else {
+            break;
+        };
+        hasher.update(&raw);
+        version_id = serde_json::from_slice::<Value>(&raw)
+            .ok()
+            .and_then(|json| json["inheritsFrom"].as_str().map(str::to_string));
+    }
+    hasher.digest().to_string()
+}
+
+fn is_locked(lock_file: &Path, fingerprint: &str) -> bool {
+    std::fs::read_to_string(lock_file).is_ok_and(|content| content == fingerprint)
+}
+
 pub async fn complete_files(instance: &Instance, minecraft_location: &MinecraftLocation) {
-    let assets_lock_file = DATA_LOCATION
-        .get_instance_root(&instance.id)
-        .join(".conic-assets-ok");
-    let libraries_lock_file = DATA_LOCATION
-        .get_instance_root(&instance.id)
-        .join(".conic-libraries-ok");
-    if std::fs::metadata(&assets_lock_file).is_ok() {
+    let instance_root = DATA_LOCATION.get_instance_root(&instance.id);
+    let assets_lock_file = instance_root.join(ASSETS_LOCK_FILE);
+    let libraries_lock_file = instance_root.join(LIBRARIES_LOCK_FILE);
+    let fingerprint = version_fingerprint(minecraft_location, &instance.get_version_id());
+    if is_locked(&assets_lock_file, &fingerprint) {
         info!("Found file \".conic-assets-ok\", no need to check assets files.");
     } else {
         info!("Checking and completing assets files");
         complete_assets_files(instance, minecraft_location).await;
         info!("Saving assets lock file");
-        std::fs::write(assets_lock_file, "ok").unwrap();
+        std::fs::write(assets_lock_file, &fingerprint).unwrap();
     }
-    if std::fs::metadata(&libraries_lock_file).is_ok() {
-        info!("Found file \".conic-libraries-ok\", no need to check libraries files.");
+    if is_locked(&libraries_lock_file, &fingerprint) {
+        info!("Found file {LIBRARIES_LOCK_FILE:?}, no need to check libraries files.");
     } else {
         info!("Checking and completing libraries files");
         complete_libraries_files(instance, minecraft_location).await;
         info!("Saving libraries lock file");
-        std::fs::write(libraries_lock_file, "ok").unwrap();
+        std::fs::write(libraries_lock_file, &fingerprint).unwrap();
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum FileProblem {
+    Missing,
+    /// The sha1 or the size doesn't match
+    Corrupted,
+}
+
+#[derive(Clone, Serialize)]
+pub struct BrokenFile {
+    pub file: PathBuf,
+    pub url: String,
+    pub problem: FileProblem,
+}
+
+#[derive(Clone, Serialize)]
+pub struct VerifyReport {
+    pub checked: usize,
+    pub broken: Vec<BrokenFile>,
+    /// The broken files have been downloaded again
+    pub repaired: bool,
+}
+
+/// Check all the files of the instance even if the lock files exist
+#[tauri::command(async)]
+pub async fn verify_instance(instance: Instance, repair: bool) -> Result<VerifyReport, ()> {
+    let minecraft_location = MinecraftLocation::new(&DATA_LOCATION.root);
+    verify_files(
+        &instance,
+        &minecraft_location,
+        repair,
+        TauriEventSink::shared(),
+    )
+    .await
+    .map_err(|err| {
+        error!(
+            "Failed to verify the files of {}: {err}",
+            instance.config.name
+        );
+    })
+}
+
+/// Check the client jar, libraries, natives, asset index and assets against their sha1 or
+/// size, and download the broken ones again if `repair` is set
+pub async fn verify_files(
+    instance: &Instance,
+    minecraft_location: &MinecraftLocation,
+    repair: bool,
+    sink: SharedEventSink,
+) -> anyhow::Result<VerifyReport> {
+    let version_id = instance.get_version_id();
+    let version = Version::from_versions_folder(minecraft_location, &version_id)?
+        .parse(minecraft_location, &[])
+        .await?;
+    let mut downloads = vec![];
+    if let Some(client) = version.downloads.get("client") {
+        let jar_version = version.inheritances.last().unwrap_or(&version.id);
+        downloads.push(Download {
+            url: client.url.clone(),
+            file: minecraft_location.get_version_jar(jar_version, None),
+            sha1: Some(client.sha1.clone()),
+            size: Some(client.size),
+        });
+    }
+    downloads.extend(generate_libraries_downloads(
+        &version.libraries,
+        minecraft_location,
+    ));
+    if let Some(asset_index) = version.asset_index.clone() {
+        downloads.extend(read_assets_downloads(asset_index, minecraft_location).await?);
+    }
+    let mut broken: Vec<(Download, FileProblem)> = downloads
+        .par_iter()
+        .filter_map(|download| Some((download.clone(), check_file(download)?)))
+        .collect();
+    let log_config = minecraft_location.get_log_config(&version.id);
+    let log_config_problem = match std::fs::read(&log_config) {
+        Ok(content) if content == LOF4J2_CONFIGURATION => None,
+        Ok(_) => Some(FileProblem::Corrupted),
+        Err(_) => Some(FileProblem::Missing),
+    };
+    let checked = downloads.len() + 1;
+    info!(
+        "Checked {checked} files of {}, {} broken",
+        instance.config.name,
+        broken.len() + log_config_problem.iter().count()
+    );
+    let mut report = VerifyReport {
+        checked,
+        broken: broken
+            .iter()
+            .map(|(download, problem)| BrokenFile {
+                file: download.file.clone(),
+                url: download.url.clone(),
+                problem: problem.clone(),
+            })
+            .collect(),
+        repaired: false,
+    };
+    if let Some(problem) = log_config_problem.clone() {
+        report.broken.push(BrokenFile {
+            file: log_config,
+            url: String::new(),
+            problem,
+        });
+    }
+    if repair && !report.broken.is_empty() {
+        if log_config_problem.is_some() {
+            generate_log4j2_configuration_file(&version, minecraft_location).await?;
+        }
+        if !broken.is_empty() {
+            let downloads = broken.drain(..).map(|(download, _)| download).collect();
+            let name = format!("Repair {}", instance.config.name);
+            download_files(&name, None, downloads, sink, true, true).await?;
+        }
+        report.repaired = true;
     }
+    if report.broken.is_empty() || report.repaired {
+        let instance_root = DATA_LOCATION.get_instance_root(&instance.id);
+        let fingerprint = version_fingerprint(minecraft_location, &version_id);
+        std::fs::create_dir_all(&instance_root)?;
+        std::fs::write(instance_root.join(ASSETS_LOCK_FILE), &fingerprint)?;
+        std::fs::write(instance_root.join(LIBRARIES_LOCK_FILE), &fingerprint)?;
+    }
+    Ok(report)
+}
+
+/// The asset index is read from the disk if its sha1 is right, or downloaded otherwise
+async fn read_assets_downloads(
+    asset_index: AssetIndex,
+    minecraft_location: &MinecraftLocation,
+) -> anyhow::Result<Vec<Download>> {
+    let index = Download {
+        url: asset_index.url.clone(),
+        file: minecraft_location.get_assets_index(&asset_index.id),
+        sha1: asset_index.sha1,
+        size: Some(asset_index.size),
+    };
+    let raw = match check_file(&index) {
+        None => tokio::fs::read_to_string(&index.file).await?,
+        Some(_) => {
+            let raw = mirror::get(&index.url).await?.text().await?;
+            if let Some(sha1) = &index.sha1 {
+                if !sha1_smol::Sha1::from(&raw)
+                    .digest()
+                    .to_string()
+                    .eq_ignore_ascii_case(sha1)
+                {
+                    return Err(anyhow!(
+                        "sha1 check failed for asset index {}",
+                        asset_index.id
+                    ));
+                }
+            }
+            raw
+        }
+    };
+    let mut downloads = generate_asset_objects_downloads(&raw, minecraft_location)?;
+    downloads.push(index);
+    Ok(downloads)
+}
+
+fn check_file(download: &Download) -> Option<FileProblem> {
+    let Ok(mut file) = std::fs::File::open(&download.file) else {
+        return Some(FileProblem::Missing);
+    };
+    let corrupted = match (&download.sha1, download.size) {
+        (Some(sha1), _) => !calculate_sha1_from_read(&mut file).eq_ignore_ascii_case(sha1),
+        (None, Some(size)) => !file.metadata().is_ok_and(|metadata| metadata.len() == size),
+        (None, None) => false,
+    };
+    corrupted.then_some(FileProblem::Corrupted)
 }
 
 async fn complete_assets_files(instance: &Instance, minecraft_location: &MinecraftLocation) {
@@ -54,7 +263,7 @@ async fn complete_assets_files(instance: &Instance, minecraft_location: &Minecra
 
     let downloads = filter_correct_files(assets_downloads).await;
     if !downloads.is_empty() {
-        download_files(downloads).await.unwrap();
+        download_missing_files(downloads).await.unwrap();
     }
 }
 
@@ -65,7 +274,7 @@ async fn complete_libraries_files(instance: &Instance, minecraft_location: &Mine
     let library_downloads = generate_libraries_downloads(&version.libraries, minecraft_location);
     let downloads = filter_correct_files(library_downloads).await;
     if !downloads.is_empty() {
-        download_files(downloads).await.unwrap();
+        download_missing_files(downloads).await.unwrap();
     }
 }
 
@@ -104,7 +313,7 @@ fn calculate_sha1_from_read<R: Read>(source: &mut R) -> String {
     hasher.digest().to_string()
 }
 
-async fn download_files(downloads: Vec<Download>) -> anyhow::Result<()> {
+async fn download_missing_files(downloads: Vec<Download>) -> anyhow::Result<()> {
     for download in downloads {
         let mut retried = 0;
         while retried <= 5 {
@@ -119,26 +328,73 @@ async fn download_files(downloads: Vec<Download>) -> anyhow::Result<()> {
 }
 
 async fn download_and_check(download: &Download) -> anyhow::Result<()> {
-    let file_path = download.file.clone();
-    tokio::fs::create_dir_all(file_path.parent().ok_or(anyhow::Error::msg(
-        "Unknown Error in instance/mod.rs".to_string(),
-    ))?)
-    .await?;
-    let mut response = HTTP_CLIENT.get(download.url.clone()).send().await?;
-    if !response.status().is_success() {
-        return Err(anyhow!("Downloaded failed"));
+    download_resumable(download, &Transfers::default(), &|| false).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::temp_dir::TempDir;
+
+    #[test]
+    fn check_sha1_and_size() {
+        let root = TempDir::new();
+        let location = MinecraftLocation::new(&*root);
+        let file = location.root.join("client.jar");
+        let mut download = Download {
+            url: "https://example.com/client.jar".to_string(),
+            file: file.clone(),
+            sha1: Some(sha1_smol::Sha1::from("jar").digest().to_string()),
+            size: Some(3),
+        };
+        assert_eq!(check_file(&download), Some(FileProblem::Missing));
+        std::fs::write(&file, "jar").unwrap();
+        assert_eq!(check_file(&download), None);
+        std::fs::write(&file, "bad").unwrap();
+        assert_eq!(check_file(&download), Some(FileProblem::Corrupted));
+        download.sha1 = None;
+        assert_eq!(check_file(&download), None);
+        std::fs::write(&file, "broken").unwrap();
+        assert_eq!(check_file(&download), Some(FileProblem::Corrupted));
     }
-    let mut file = tokio::fs::File::create(&file_path).await?;
-    while let Some(chunk) = response.chunk().await? {
-        file.write_all(&chunk).await?;
+
+    #[test]
+    fn fingerprint_changes_with_inherited_versions() {
+        let root = TempDir::new();
+        let location = MinecraftLocation::new(&*root);
+        let write = |id: &str, json: &str| {
+            let path = location.get_version_json(id);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, json).unwrap();
+        };
+        write("1.20.1", r#"{"id":"1.20.1"}"#);
+        write("fabric", r#"{"id":"fabric","inheritsFrom":"1.20.1"}"#);
+        let fingerprint = version_fingerprint(&location, "fabric");
+        assert_eq!(fingerprint, version_fingerprint(&location, "fabric"));
+        write("1.20.1", r#"{"id":"1.20.1","type":"release"}"#);
+        assert_ne!(fingerprint, version_fingerprint(&location, "fabric"));
     }
-    file.sync_all().await?;
-    drop(file);
-    let mut file = std::fs::File::open(&file_path).unwrap();
-    if let Some(sha1) = download.sha1.clone() {
-        if calculate_sha1_from_read(&mut file) != sha1 {
-            return Err(anyhow::Error::msg("sha1 check failed".to_string()));
-        }
+
+    #[test]
+    fn fingerprint_of_cyclic_versions() {
+        let root = TempDir::new();
+        let location = MinecraftLocation::new(&*root);
+        let write = |id: &str, json: &str| {
+            let path = location.get_version_json(id);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, json).unwrap();
+        };
+        write("a", r#"{"id":"a","inheritsFrom":"b"}"#);
+        write("b", r#"{"id":"b","inheritsFrom":"a"}"#);
+        write("self", r#"{"id":"self","inheritsFrom":"self"}"#);
+        assert_eq!(
+            version_fingerprint(&location, "a"),
+            version_fingerprint(&location, "a")
+        );
+        assert_ne!(
+            version_fingerprint(&location, "self"),
+            version_fingerprint(&location, "a")
+        );
     }
-    Ok(())
 }
@@ -0,0 +1,339 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Find out why the game crashed from the game output and crash reports
+
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub enum CrashCategory {
+    WrongJavaVersion,
+    OutOfMemory,
+    MissingModDependency,
+    DuplicateMod,
+    MixinFailure,
+    CorruptedLibrary,
+    OpenGLError,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CrashCause {
+    pub category: CrashCategory,
+    /// The mod id, class or file that is suspected to cause the crash
+    pub suspect: Option<String>,
+    /// The line matched the rule
+    pub evidence: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct CrashAnalysis {
+    pub instance_id: Uuid,
+    pub exit_code: Option<i32>,
+    pub causes: Vec<CrashCause>,
+    pub crash_reports: Vec<PathBuf>,
+}
+
+struct CrashRule {
+    category: CrashCategory,
+    pattern: Regex,
+    /// The capture group of the suspected mod or file
+    suspect_group: Option<usize>,
+}
+
+impl CrashRule {
+    fn new(category: CrashCategory, pattern: &str, suspect_group: Option<usize>) -> Self {
+        Self {
+            category,
+            pattern: Regex::new(pattern).unwrap(),
+            suspect_group,
+        }
+    }
+}
+
+/// The rules are ordered by priority, the first cause is the most likely one
+static CRASH_RULES: Lazy<Vec<CrashRule>> = Lazy::new(|| {
+    use CrashCategory::*;
+    vec![
+        CrashRule::new(
+            WrongJavaVersion,
+            r"java\.lang\.UnsupportedClassVersionError: (\S+) has been compiled by a more recent version",
+            Some(1),
+        ),
+        CrashRule::new(
+            WrongJavaVersion,
+            r"Unsupported class file major version \d+",
+            None,
+        ),
+        CrashRule::new(
+            WrongJavaVersion,
+            r"class jdk\.internal\.loader\.ClassLoaders\$AppClassLoader cannot be cast to class java\.net\.URLClassLoader",
+            None,
+        ),
+        CrashRule::new(OutOfMemory, r"java\.lang\.OutOfMemoryError(?:: .*)?", None),
+        CrashRule::new(
+            OutOfMemory,
+            r"Could not reserve enough space for .*object heap|There is insufficient memory for the Java Runtime Environment",
+            None,
+        ),
+        CrashRule::new(
+            MissingModDependency,
+            r"Mod '[^']+' \(([\w-]+)\) \S+ requires .*",
+            Some(1),
+        ),
+        CrashRule::new(
+            MissingModDependency,
+            r"Mod ID: '[\w-]+', Requested by: '([\w-]+)'.*",
+            Some(1),
+        ),
+        CrashRule::new(
+            MissingModDependency,
+            r"MissingModsException: Mod ([\w-]+) \(.*\) requires .*",
+            Some(1),
+        ),
+        CrashRule::new(
+            DuplicateMod,
+            r"Mod ID: '([\w-]+)' from mod files: .*",
+            Some(1),
+        ),
+        CrashRule::new(
+            DuplicateMod,
+            r"Mod ID '([\w-]+)' has multiple candidates.*",
+            Some(1),
+        ),
+        CrashRule::new(DuplicateMod, r"Duplicate mods? found.*?'([\w-]+)'", Some(1)),
+        CrashRule::new(DuplicateMod, r"DuplicateModsFoundException.*", None),
+        CrashRule::new(
+            MixinFailure,
+            r"Mixin apply for mod ([\w-]+) failed.*",
+            Some(1),
+        ),
+        CrashRule::new(
+            MixinFailure,
+            r"\S+\.mixins\.json:\S+ from mod ([\w-]+).*",
+            Some(1),
+        ),
+        CrashRule::new(
+            MixinFailure,
+            r"org\.spongepowered\.asm\.mixin\.\S*(?:InvalidMixinException|MixinApplyError|MixinTransformerError).*",
+            None,
+        ),
+        CrashRule::new(
+            CorruptedLibrary,
+            r"(?:java\.util\.zip\.ZipException|Invalid or corrupt jarfile|zip END header not found).*?([^\s/\\]+\.jar)",
+            Some(1),
+        ),
+        CrashRule::new(CorruptedLibrary, r"java\.util\.zip\.ZipException: .*", None),
+        CrashRule::new(
+            CorruptedLibrary,
+            r"Error: Could not find or load main class (\S+)",
+            Some(1),
+        ),
+        CrashRule::new(
+            OpenGLError,
+            r"C  \[((?:ig\w+icd\w*|atio6axx|atioglxx|atig6pxx|nvoglv\w+|amdxc\w*|libGL\w*)\.\w+)",
+            Some(1),
+        ),
+        CrashRule::new(
+            OpenGLError,
+            r"Pixel format not accelerated|GLFW error 6554[2-5].*|WGL: The driver does not appear to support OpenGL|Failed to create the GLFW window|Couldn't set pixel format",
+            None,
+        ),
+    ]
+});
+
+/// Match the text with the rules, return every cause found
+pub fn analyze(text: &str) -> Vec<CrashCause> {
+    let mut result: Vec<CrashCause> = vec![];
+    for rule in CRASH_RULES.iter() {
+        for captures in rule.pattern.captures_iter(text) {
+            let suspect = rule
+                .suspect_group
+                .and_then(|group| captures.get(group))
+                .map(|x| x.as_str().to_string());
+            let duplicated = result
+                .iter()
+                .any(|cause| cause.category == rule.category && cause.suspect == suspect);
+            // A rule without suspect is only a fallback of the same category
+            let covered =
+                suspect.is_none() && result.iter().any(|cause| cause.category == rule.category);
+            if duplicated || covered {
+                continue;
+            }
+            result.push(CrashCause {
+                category: rule.category.clone(),
+                suspect,
+                evidence: captures[0].trim().to_string(),
+            });
+        }
+    }
+    result
+}
+
+/// Get the crash reports created after the game started
+pub fn find_crash_reports(instance_root: &Path, since: SystemTime) -> Vec<PathBuf> {
+    let entries = match std::fs::read_dir(instance_root.join("crash-reports")) {
+        Ok(x) => x,
+        Err(_) => return vec![],
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(|modified| modified >= since)
+        })
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|x| x == "txt"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD_FORGE_ON_NEW_JAVA: &str = r#"
+[main/INFO]: Loading tweak class name net.minecraftforge.fml.common.launcher.FMLTweaker
+Exception in thread "main" java.lang.ClassCastException: class jdk.internal.loader.ClassLoaders$AppClassLoader cannot be cast to class java.net.URLClassLoader (jdk.internal.loader.ClassLoaders$AppClassLoader and java.net.URLClassLoader are in module java.base of loader 'bootstrap')
+	at net.minecraft.launchwrapper.Launch.<init>(Launch.java:34)
+"#;
+
+    const CLASS_VERSION: &str = r#"
+Exception in thread "main" java.lang.UnsupportedClassVersionError: net/minecraft/client/main/Main has been compiled by a more recent version of the Java Runtime (class file version 65.0), this version of the Java Runtime only recognizes class file versions up to 52.0
+"#;
+
+    const OUT_OF_MEMORY: &str = r#"
+[Render thread/ERROR]: Reported exception thrown!
+java.lang.OutOfMemoryError: Java heap space
+	at java.base/java.util.Arrays.copyOf(Arrays.java:3537)
+"#;
+
+    const FABRIC_MISSING_DEPENDENCY: &str = r#"
+[main/ERROR]: Incompatible mods found!
+net.fabricmc.loader.impl.FormattedException: Some of your mods are incompatible with the game or each other!
+A potential solution has been determined:
+	 - Install fabric-api, any version.
+Unmet dependency listing:
+	 - Mod 'Mod Menu' (modmenu) 7.2.2 requires any version of fabric-api, which is missing!
+"#;
+
+    const FORGE_MISSING_DEPENDENCY: &str = r#"
+[main/ERROR] [net.minecraftforge.fml.loading.ModSorter/LOADING]: Missing or unsupported mandatory dependencies:
+	Mod ID: 'geckolib', Requested by: 'alexsmobs', Expected range: '[4.2,)', Actual version: '[MISSING]'
+"#;
+
+    const FORGE_DUPLICATE_MOD: &str = r#"
+[main/ERROR] [net.minecraftforge.fml.loading.ModSorter/LOADING]: Found duplicate mods:
+	Mod ID: 'jei' from mod files: jei-1.20.1-forge-15.2.0.27.jar, jei-1.20.1-forge-15.3.0.4.jar
+"#;
+
+    const FABRIC_DUPLICATE_MOD: &str = r#"
+[main/ERROR]: Incompatible mods found!
+net.fabricmc.loader.impl.discovery.ModResolutionException: Duplicate mods found for 'sodium': sodium-fabric-0.5.3.jar, sodium-fabric-0.5.8.jar
+"#;
+
+    const MIXIN_FAILURE: &str = r#"
+[Render thread/ERROR]: Mixin apply for mod sodium failed sodium.mixins.json:core.MixinWindow from mod sodium -> net.minecraft.class_1041: org.spongepowered.asm.mixin.injection.throwables.InvalidInjectionException Critical injection failure
+org.spongepowered.asm.mixin.transformer.throwables.MixinTransformerError: An unexpected critical error was encountered
+"#;
+
+    const CORRUPTED_LIBRARY: &str = r#"
+Exception in thread "main" java.util.zip.ZipException: zip END header not found
+	at java.base/java.util.zip.ZipFile$Source.zerror(ZipFile.java:1607)
+Caused by: java.util.zip.ZipException: invalid LOC header (bad signature) in /home/user/.conic/libraries/org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1.jar
+"#;
+
+    const OPENGL_ERROR: &str = r#"
+#
+# A fatal error has been detected by the Java Runtime Environment:
+#
+#  EXCEPTION_ACCESS_VIOLATION (0xc0000005) at pc=0x00007ffc1f2ab4e0, pid=12345, tid=6789
+#
+# Problematic frame:
+# C  [ig9icd64.dll+0x1b4e0]
+"#;
+
+    fn categories(text: &str) -> Vec<CrashCategory> {
+        analyze(text).into_iter().map(|x| x.category).collect()
+    }
+
+    #[test]
+    fn wrong_java_version() {
+        assert_eq!(
+            categories(OLD_FORGE_ON_NEW_JAVA),
+            vec![CrashCategory::WrongJavaVersion]
+        );
+        let causes = analyze(CLASS_VERSION);
+        assert_eq!(causes[0].category, CrashCategory::WrongJavaVersion);
+        assert_eq!(
+            causes[0].suspect.as_deref(),
+            Some("net/minecraft/client/main/Main")
+        );
+    }
+
+    #[test]
+    fn out_of_memory() {
+        assert_eq!(categories(OUT_OF_MEMORY), vec![CrashCategory::OutOfMemory]);
+    }
+
+    #[test]
+    fn missing_mod_dependency() {
+        let causes = analyze(FABRIC_MISSING_DEPENDENCY);
+        assert_eq!(causes.len(), 1);
+        assert_eq!(causes[0].category, CrashCategory::MissingModDependency);
+        assert_eq!(causes[0].suspect.as_deref(), Some("modmenu"));
+        let causes = analyze(FORGE_MISSING_DEPENDENCY);
+        assert_eq!(causes[0].category, CrashCategory::MissingModDependency);
+        assert_eq!(causes[0].suspect.as_deref(), Some("alexsmobs"));
+    }
+
+    #[test]
+    fn duplicate_mod() {
+        let causes = analyze(FORGE_DUPLICATE_MOD);
+        assert_eq!(causes.len(), 1);
+        assert_eq!(causes[0].category, CrashCategory::DuplicateMod);
+        assert_eq!(causes[0].suspect.as_deref(), Some("jei"));
+        let causes = analyze(FABRIC_DUPLICATE_MOD);
+        assert_eq!(causes.len(), 1);
+        assert_eq!(causes[0].category, CrashCategory::DuplicateMod);
+        assert_eq!(causes[0].suspect.as_deref(), Some("sodium"));
+    }
+
+    #[test]
+    fn mixin_failure() {
+        let causes = analyze(MIXIN_FAILURE);
+        assert_eq!(causes.len(), 1);
+        assert_eq!(causes[0].category, CrashCategory::MixinFailure);
+        assert_eq!(causes[0].suspect.as_deref(), Some("sodium"));
+    }
+
+    #[test]
+    fn corrupted_library() {
+        let causes = analyze(CORRUPTED_LIBRARY);
+        assert_eq!(causes.len(), 1);
+        assert_eq!(causes[0].category, CrashCategory::CorruptedLibrary);
+        assert_eq!(causes[0].suspect.as_deref(), Some("lwjgl-3.3.1.jar"));
+    }
+
+    #[test]
+    fn opengl_error() {
+        let causes = analyze(OPENGL_ERROR);
+        assert_eq!(causes.len(), 1);
+        assert_eq!(causes[0].category, CrashCategory::OpenGLError);
+        assert_eq!(causes[0].suspect.as_deref(), Some("ig9icd64.dll"));
+    }
+
+    #[test]
+    fn normal_output() {
+        assert!(analyze("[Render thread/INFO]: Stopping!\n").is_empty());
+    }
+}
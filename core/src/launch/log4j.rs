@@ -0,0 +1,259 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Parse the XML log events printed by the client.
+//!
+//! The events look like this, one event may take several lines:
+//!
+//! ```xml
+//! <log4j:Event logger="net.minecraft.client.Minecraft" timestamp="1700000000000" level="INFO" thread="Render thread">
+//!   <log4j:Message><![CDATA[Setting user: Steve]]></log4j:Message>
+//! </log4j:Event>
+//! ```
+
+use quick_xml::{events::Event, Reader};
+use serde::Serialize;
+
+#[derive(Clone, Debug, Serialize, PartialEq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl LogLevel {
+    fn parse(level: &str) -> Option<Self> {
+        match level.to_uppercase().as_str() {
+            "TRACE" => Some(Self::Trace),
+            "DEBUG" => Some(Self::Debug),
+            "INFO" => Some(Self::Info),
+            "WARN" => Some(Self::Warn),
+            "ERROR" => Some(Self::Error),
+            "FATAL" => Some(Self::Fatal),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, PartialEq)]
+pub struct LogRecord {
+    /// Unix timestamp in milliseconds
+    pub time: Option<u64>,
+    pub level: Option<LogLevel>,
+    pub thread: Option<String>,
+    pub logger: Option<String>,
+    pub message: String,
+    pub throwable: Option<String>,
+}
+
+impl LogRecord {
+    /// Format the record like the vanilla console output
+    pub fn to_plain_text(&self) -> String {
+        let level = match &self.level {
+            Some(x) => format!("{x:?}").to_uppercase(),
+            None => "UNKNOWN".to_string(),
+        };
+        let mut text = format!(
+            "[{}/{}]: {}",
+            self.thread.as_deref().unwrap_or("unknown"),
+            level,
+            self.message
+        );
+        if let Some(throwable) = &self.throwable {
+            text.push('\n');
+            text.push_str(throwable.trim_end());
+        }
+        text
+    }
+}
+
+pub enum LogLine {
+    Plain(String),
+    Record(LogRecord),
+}
+
+/// Feed the output line by line, the lines of an unfinished event are kept until it ends
+#[derive(Default)]
+pub struct Log4jParser {
+    buffer: Option<Vec<String>>,
+}
+
+impl Log4jParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn feed(&mut self, line: &str) -> Vec<LogLine> {
+        let buffer = match &mut self.buffer {
+            Some(x) => x,
+            None if is_event_start(line) => self.buffer.insert(vec![]),
+            None => return vec![LogLine::Plain(line.to_string())],
+        };
+        buffer.push(line.to_string());
+        if !line.contains("</log4j:Event>") && !line.contains("</Event>") {
+            return vec![];
+        }
+        let lines = self.buffer.take().unwrap_or_default();
+        match parse_event(&lines.join("\n")) {
+            Some(record) => vec![LogLine::Record(record)],
+            None => lines.into_iter().map(LogLine::Plain).collect(),
+        }
+    }
+
+    /// Return the lines of the unfinished event as plain text
+    pub fn finish(&mut self) -> Vec<LogLine> {
+        self.buffer
+            .take()
+            .unwrap_or_default()
+            .into_iter()
+            .map(LogLine::Plain)
+            .collect()
+    }
+}
+
+fn is_event_start(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("<log4j:Event ") || line.starts_with("<Event ")
+}
+
+fn parse_event(xml: &str) -> Option<LogRecord> {
+    enum Field {
+        None,
+        Message,
+        Throwable,
+    }
+    let mut reader = Reader::from_str(xml);
+    let mut record = LogRecord::default();
+    let mut field = Field::None;
+    let mut throwable = String::new();
+    let mut finished = false;
+    loop {
+        match reader.read_event().ok()? {
+            Event::Start(e) if e.local_name().as_ref() == b"Event" => {
+                for attribute in e.attributes().with_checks(false) {
+                    let attribute = attribute.ok()?;
+                    let value = attribute.unescape_value().ok()?.to_string();
+                    match attribute.key.local_name().as_ref() {
+                        b"logger" | b"loggerName" => record.logger = Some(value),
+                        b"timestamp" | b"timeMillis" => record.time = value.parse().ok(),
+                        b"level" => record.level = LogLevel::parse(&value),
+                        b"thread" => record.thread = Some(value),
+                        _ => (),
+                    }
+                }
+            }
+            Event::Start(e) => {
+                field = match e.local_name().as_ref() {
+                    b"Message" => Field::Message,
+                    b"Throwable" | b"Thrown" => Field::Throwable,
+                    _ => Field::None,
+                }
+            }
+            Event::CData(e) => {
+                let text = String::from_utf8_lossy(&e).to_string();
+                match field {
+                    Field::Message => record.message.push_str(&text),
+                    Field::Throwable => throwable.push_str(&text),
+                    Field::None => (),
+                }
+            }
+            Event::Text(e) => {
+                let text = e.unescape().ok()?;
+                match field {
+                    Field::Message => record.message.push_str(&text),
+                    Field::Throwable => throwable.push_str(&text),
+                    Field::None => (),
+                }
+            }
+            Event::End(e) if e.local_name().as_ref() == b"Event" => finished = true,
+            Event::End(_) => field = Field::None,
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+    if !finished {
+        return None;
+    }
+    if !throwable.trim().is_empty() {
+        record.throwable = Some(throwable);
+    }
+    Some(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_all(parser: &mut Log4jParser, output: &str) -> Vec<LogLine> {
+        output.lines().flat_map(|line| parser.feed(line)).collect()
+    }
+
+    #[test]
+    fn parse_multiline_event() {
+        let output = r#"<log4j:Event logger="net.minecraft.client.Minecraft" timestamp="1700000000000" level="INFO" thread="Render thread">
+  <log4j:Message><![CDATA[Setting user: Steve]]></log4j:Message>
+</log4j:Event>"#;
+        let lines = feed_all(&mut Log4jParser::new(), output);
+        assert_eq!(lines.len(), 1);
+        let LogLine::Record(record) = &lines[0] else {
+            panic!("not parsed");
+        };
+        assert_eq!(record.time, Some(1700000000000));
+        assert_eq!(record.level, Some(LogLevel::Info));
+        assert_eq!(record.thread.as_deref(), Some("Render thread"));
+        assert_eq!(
+            record.logger.as_deref(),
+            Some("net.minecraft.client.Minecraft")
+        );
+        assert_eq!(record.message, "Setting user: Steve");
+        assert_eq!(record.throwable, None);
+        assert_eq!(
+            record.to_plain_text(),
+            "[Render thread/INFO]: Setting user: Steve"
+        );
+    }
+
+    #[test]
+    fn parse_throwable() {
+        let output = r#"<log4j:Event logger="net.minecraft.server.MinecraftServer" timestamp="1700000000001" level="ERROR" thread="Server thread">
+  <log4j:Message><![CDATA[Encountered an unexpected exception]]></log4j:Message>
+  <log4j:Throwable><![CDATA[java.lang.NullPointerException: Cannot invoke "Object.toString()"
+	at net.minecraft.server.MinecraftServer.tick(MinecraftServer.java:100)
+]]></log4j:Throwable>
+</log4j:Event>"#;
+        let lines = feed_all(&mut Log4jParser::new(), output);
+        let LogLine::Record(record) = &lines[0] else {
+            panic!("not parsed");
+        };
+        assert_eq!(record.level, Some(LogLevel::Error));
+        assert!(record
+            .throwable
+            .as_deref()
+            .unwrap()
+            .starts_with("java.lang.NullPointerException"));
+    }
+
+    #[test]
+    fn plain_text_fall_through() {
+        let output = "[12:00:00] [main/INFO]: Loading Minecraft 1.20.1\n<not an event>";
+        let lines = feed_all(&mut Log4jParser::new(), output);
+        assert_eq!(lines.len(), 2);
+        assert!(
+            matches!(&lines[0], LogLine::Plain(x) if x == "[12:00:00] [main/INFO]: Loading Minecraft 1.20.1")
+        );
+        assert!(matches!(&lines[1], LogLine::Plain(x) if x == "<not an event>"));
+    }
+
+    #[test]
+    fn broken_event_fall_through() {
+        let mut parser = Log4jParser::new();
+        let output = "<log4j:Event logger=\"a\" level=\"INFO\" thread=\"main\">\n<log4j:Message><![CDATA[cut";
+        assert!(feed_all(&mut parser, output).is_empty());
+        assert_eq!(parser.finish().len(), 2);
+    }
+}
@@ -0,0 +1,252 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Save the game output of every launch to the logs folder.
+//!
+//! Each instance has a folder in `logs/instances`, a new file is created for every session,
+//! and the logs of the previous sessions are compressed with gzip.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use log::warn;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::DATA_LOCATION;
+
+/// Only keep the logs of the latest sessions
+const MAX_SESSION_LOGS: usize = 20;
+
+#[derive(Clone, Serialize)]
+pub struct SessionLogInfo {
+    pub name: String,
+    /// Unix timestamp in seconds
+    pub start_time: u64,
+    pub size: u64,
+    pub compressed: bool,
+    /// Sessions started in the same second are numbered from 1
+    #[serde(skip)]
+    sequence: u32,
+}
+
+#[derive(Clone)]
+pub struct SessionLog {
+    pub path: PathBuf,
+    writer: Arc<Mutex<BufWriter<File>>>,
+}
+
+impl SessionLog {
+    /// Rotate the old logs and create the log file of a new session
+    pub fn create(instance_id: &Uuid) -> std::io::Result<Self> {
+        Self::create_in(&get_instance_logs_folder(instance_id))
+    }
+
+    fn create_in(folder: &Path) -> std::io::Result<Self> {
+        std::fs::create_dir_all(folder)?;
+        rotate(folder);
+        let start_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|x| x.as_secs())
+            .unwrap_or_default();
+        // `<start_time>.log`, then `<start_time>-1.log` and so on if it has been taken
+        for sequence in 0.. {
+            let name = match sequence {
+                0 => format!("{start_time}.log"),
+                _ => format!("{start_time}-{sequence}.log"),
+            };
+            let path = folder.join(&name);
+            if folder.join(format!("{name}.gz")).exists() {
+                continue;
+            }
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => {
+                    return Ok(Self {
+                        path,
+                        writer: Arc::new(Mutex::new(BufWriter::new(file))),
+                    })
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        unreachable!()
+    }
+
+    pub fn write_line(&self, line: &str) {
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(e) = writeln!(writer, "{line}") {
+            warn!("Could not write game log: {e}");
+        }
+    }
+
+    pub fn flush(&self) {
+        let _ = self.writer.lock().unwrap().flush();
+    }
+}
+
+pub fn get_instance_logs_folder(instance_id: &Uuid) -> PathBuf {
+    DATA_LOCATION
+        .logs
+        .join("instances")
+        .join(instance_id.to_string())
+}
+
+/// Compress the plain text logs and remove the oldest ones
+fn rotate(folder: &Path) {
+    let entries = match std::fs::read_dir(folder) {
+        Ok(x) => x,
+        Err(_) => return,
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if path.extension().is_some_and(|x| x == "log") {
+            if let Err(e) = compress(&path) {
+                warn!("Could not compress {}: {e}", path.display());
+            }
+        }
+    }
+    let mut logs = list_logs(folder);
+    if logs.len() >= MAX_SESSION_LOGS {
+        logs.sort_by_key(|log| (log.start_time, log.sequence));
+        for log in &logs[..=logs.len() - MAX_SESSION_LOGS] {
+            let _ = std::fs::remove_file(folder.join(&log.name));
+        }
+    }
+}
+
+fn compress(path: &Path) -> std::io::Result<()> {
+    let mut gz_path = path.as_os_str().to_owned();
+    gz_path.push(".gz");
+    let mut encoder = GzEncoder::new(File::create(&gz_path)?, Compression::default());
+    std::io::copy(&mut File::open(path)?, &mut encoder)?;
+    encoder.finish()?;
+    std::fs::remove_file(path)
+}
+
+fn list_logs(folder: &Path) -> Vec<SessionLogInfo> {
+    let entries = match std::fs::read_dir(folder) {
+        Ok(x) => x,
+        Err(_) => return vec![],
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let (stem, compressed) = match name.strip_suffix(".log.gz") {
+                Some(x) => (x, true),
+                None => (name.strip_suffix(".log")?, false),
+            };
+            let (start_time, sequence) = match stem.split_once('-') {
+                Some((start_time, sequence)) => (start_time.parse().ok()?, sequence.parse().ok()?),
+                None => (stem.parse().ok()?, 0),
+            };
+            Some(SessionLogInfo {
+                size: entry.metadata().ok()?.len(),
+                name,
+                start_time,
+                compressed,
+                sequence,
+            })
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn list_instance_logs(instance_id: Uuid) -> Vec<SessionLogInfo> {
+    let mut logs = list_logs(&get_instance_logs_folder(&instance_id));
+    logs.sort_by_key(|log| std::cmp::Reverse((log.start_time, log.sequence)));
+    logs
+}
+
+#[tauri::command(async)]
+pub async fn read_instance_log(instance_id: Uuid, name: String) -> Result<String, ()> {
+    let folder = get_instance_logs_folder(&instance_id);
+    let log = match list_logs(&folder).into_iter().find(|log| log.name == name) {
+        Some(x) => x,
+        None => {
+            warn!("Log {name} not found");
+            return Err(());
+        }
+    };
+    let file = File::open(folder.join(&log.name)).map_err(|_| ())?;
+    let mut content = String::new();
+    let result = if log.compressed {
+        GzDecoder::new(file).read_to_string(&mut content)
+    } else {
+        let mut file = file;
+        file.read_to_string(&mut content)
+    };
+    match result {
+        Ok(_) => Ok(content),
+        Err(e) => {
+            warn!("Could not read log {name}: {e}");
+            Err(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::temp_dir::TempDir;
+
+    fn read_gz(path: &Path) -> String {
+        let mut content = String::new();
+        GzDecoder::new(File::open(path).unwrap())
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    }
+
+    #[test]
+    fn sessions_in_the_same_second_have_their_own_files() {
+        let folder = TempDir::new();
+        let first = SessionLog::create_in(&folder).unwrap();
+        let second = SessionLog::create_in(&folder).unwrap();
+        let third = SessionLog::create_in(&folder).unwrap();
+        assert_ne!(first.path, second.path);
+        assert_ne!(second.path, third.path);
+        assert_ne!(first.path, third.path);
+        assert_eq!(list_logs(&folder).len(), 3);
+    }
+
+    #[test]
+    fn previous_sessions_are_compressed() {
+        let folder = TempDir::new();
+        let first = SessionLog::create_in(&folder).unwrap();
+        first.write_line("[main/INFO]: Setting user: Steve");
+        first.flush();
+        let second = SessionLog::create_in(&folder).unwrap();
+        assert!(!first.path.exists());
+        let mut gz_path = first.path.clone().into_os_string();
+        gz_path.push(".gz");
+        assert_eq!(
+            read_gz(Path::new(&gz_path)),
+            "[main/INFO]: Setting user: Steve\n"
+        );
+        assert!(second.path.exists());
+        let logs = list_logs(&folder);
+        assert_eq!(logs.iter().filter(|log| log.compressed).count(), 1);
+    }
+
+    #[test]
+    fn only_the_latest_sessions_are_kept() {
+        let folder = TempDir::new();
+        for start_time in 1..=25 {
+            std::fs::write(folder.join(format!("{start_time}.log.gz")), "").unwrap();
+        }
+        SessionLog::create_in(&folder).unwrap();
+        let logs = list_logs(&folder);
+        assert_eq!(logs.len(), MAX_SESSION_LOGS);
+        let oldest = logs.iter().map(|log| log.start_time).min().unwrap();
+        assert_eq!(oldest, 7);
+    }
+}
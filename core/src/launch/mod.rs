@@ -3,63 +3,101 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use std::{
-    io::BufRead,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
     process::{Command, Stdio},
-    thread,
-    time::{SystemTime, UNIX_EPOCH},
+    thread::{self, JoinHandle},
+    time::SystemTime,
 };
 
 use crate::{
-    account::{self, refresh_microsoft_account_by_uuid, Account},
-    folder::MinecraftLocation,
+    account::{self, yggdrasil, Account, AccountType},
+    config::Config,
+    event::{Event, EventSink, SharedEventSink, TauriEventSink},
     instance::Instance,
+    java::{
+        discovery::{JavaCompatibility, JavaInstallation},
+        runtime::ensure_java_runtime,
+    },
     platform::OsFamily,
     version::Version,
-    Storage, DATA_LOCATION, MAIN_WINDOW, PLATFORM_INFO,
+    Storage, DATA_LOCATION, PLATFORM_INFO,
 };
 use arguments::generate_command_arguments;
-use complete::complete_files;
-use log::{error, info, trace};
+use complete::{complete_files, LIBRARIES_LOCK_FILE};
+use crash::{CrashAnalysis, CrashCategory};
+use log::{error, info, trace, warn};
+use log4j::{Log4jParser, LogLevel, LogLine, LogRecord};
+use logs::SessionLog;
 use options::LaunchOptions;
+use process::InstanceState;
+use script::export_launch_script;
 use serde::Serialize;
-use tauri::Emitter;
 use uuid::Uuid;
 
 mod arguments;
-mod complete;
+pub mod complete;
+pub mod crash;
+pub mod log4j;
+pub mod logs;
 mod options;
+pub mod process;
+mod script;
+
+#[derive(Clone, Serialize)]
+pub struct JavaVersionMismatch {
+    pub instance_id: Uuid,
+    pub java: PathBuf,
+    pub required: i32,
+    pub found: i32,
+    pub compatibility: JavaCompatibility,
+}
 
 #[derive(Clone, Serialize)]
 pub struct Log {
     #[serde(rename = "instanceName")]
     pub instance_id: Uuid,
     pub content: String,
+    /// Only available when the game prints XML log events
+    pub level: Option<LogLevel>,
+    pub record: Option<LogRecord>,
 }
 
-async fn check_and_refresh_account(account: &Account) -> anyhow::Result<Account> {
-    info!("Checking account: {}", account.profile.uuid);
-    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
-    const AHEAD: u64 = 3600 * 4;
-    let token_deadline = match account.token_deadline {
-        Some(x) => x,
-        None => return Ok(account.clone()),
-    };
-    if now > token_deadline - AHEAD {
-        info!("The access token will expire in 4 hours");
-        let refreshed_account =
-            refresh_microsoft_account_by_uuid(account.profile.uuid.to_string()).await;
-        Ok(refreshed_account)
-    } else {
-        info!(
-            "The access token will expire in {} seconds, no need to refresh.",
-            token_deadline - now
-        );
-        Ok(account.clone())
+#[tauri::command(async)]
+pub async fn launch(storage: tauri::State<'_, Storage>, instance: Instance) -> Result<(), ()> {
+    let config = storage.config.lock().unwrap().clone();
+    start_instance(config, instance, None, TauriEventSink::shared())
+        .await
+        .map(|_| ())
+}
+
+/// Launch the instance with the given account or the selected one.
+///
+/// Returns the thread waiting for the game to exit.
+pub async fn start_instance(
+    config: Config,
+    instance: Instance,
+    account: Option<Account>,
+    sink: SharedEventSink,
+) -> Result<JoinHandle<()>, ()> {
+    let instance_id = instance.id;
+    if !process::try_reserve(instance_id, sink.clone()) {
+        error!("The instance {} is already running", instance.config.name);
+        return Err(());
     }
+    let result = launch_instance(config, instance, account, sink).await;
+    if result.is_err() {
+        process::set_state(instance_id, InstanceState::Exited { code: None });
+    }
+    result
 }
 
-#[tauri::command(async)]
-pub async fn launch(storage: tauri::State<'_, Storage>, instance: Instance) -> Result<(), ()> {
+async fn launch_instance(
+    config: Config,
+    instance: Instance,
+    account: Option<Account>,
+    sink: SharedEventSink,
+) -> Result<JoinHandle<()>, ()> {
     info!(
         "Starting Minecraft client, instance: {}",
         instance.config.name
@@ -74,24 +112,41 @@ pub async fn launch(storage: tauri::State<'_, Storage>, instance: Instance) -> R
         Some(x) => info!("-> Mod loader version: {x}"),
         None => info!("-> Mod loader version: none"),
     };
-    let config = storage.config.lock().unwrap().clone();
-    let selected_account = account::get_account_by_uuid(&config.current_account);
-    let selected_account = match selected_account.first() {
+    let selected_account = match account {
         Some(x) => x,
-        None => {
-            error!("The selected account not been found, opening account manager");
-            MAIN_WINDOW.emit("add-account", "add-account").unwrap();
-            return Err(());
-        }
+        None => match account::get_account_by_uuid(&config.current_account).first() {
+            Some(x) => x.clone(),
+            None => {
+                error!("The selected account not been found, opening account manager");
+                sink.emit(Event::AccountRequired);
+                return Err(());
+            }
+        },
     };
     let selected_account = if config.launch.skip_refresh_account {
         info!("Account refresh disabled by user");
-        selected_account.clone()
+        selected_account
     } else {
-        check_and_refresh_account(selected_account).await.unwrap()
+        match account::check_and_refresh(&selected_account, sink.as_ref()).await {
+            Ok(x) => x,
+            Err(e) => {
+                error!("Failed to refresh the account: {e}");
+                return Err(());
+            }
+        }
     };
 
-    let launch_options = LaunchOptions::new(&instance, selected_account);
+    let account_type = selected_account.account_type.clone();
+    let mut launch_options = LaunchOptions::new(&instance, selected_account);
+    if let AccountType::Yggdrasil { server } = account_type {
+        match yggdrasil::prepare_agent(&server).await {
+            Ok(x) => launch_options.yggdrasil_agent = Some(x),
+            Err(e) => {
+                error!("Failed to prepare authlib-injector: {e}");
+                return Err(());
+            }
+        }
+    }
     let minecraft_location = launch_options.minecraft_location.clone();
     if config.launch.skip_check_files {
         info!("File checking disabled by user")
@@ -105,6 +160,50 @@ pub async fn launch(storage: tauri::State<'_, Storage>, instance: Instance) -> R
         .parse(&minecraft_location, &launch_options.get_enabled_features())
         .await
         .unwrap();
+    let java = if launch_options.java_path == "auto" {
+        match ensure_java_runtime(&version.java_version, sink.clone()).await {
+            Ok(x) => x,
+            Err(e) => {
+                error!("Failed to prepare Java runtime: {e}");
+                return Err(());
+            }
+        }
+    } else {
+        PathBuf::from(&launch_options.java_path)
+    };
+    match JavaInstallation::from_executable(&java) {
+        Ok(installation) => {
+            let compatibility = installation.check_compatibility(&version.java_version);
+            let mismatch = JavaVersionMismatch {
+                instance_id: instance.id,
+                java: java.clone(),
+                required: version.java_version.major_version,
+                found: installation.major_version,
+                compatibility: compatibility.clone(),
+            };
+            match compatibility {
+                JavaCompatibility::Compatible => {
+                    info!("Using Java {} at {}", installation.version, java.display())
+                }
+                JavaCompatibility::TooNew => {
+                    warn!(
+                        "The game requires Java {}, but Java {} is used, it may crash",
+                        mismatch.required, mismatch.found
+                    );
+                    sink.emit(Event::JavaVersionMismatch(mismatch));
+                }
+                JavaCompatibility::TooOld => {
+                    error!(
+                        "The game requires Java {}, but Java {} is used",
+                        mismatch.required, mismatch.found
+                    );
+                    sink.emit(Event::JavaVersionMismatch(mismatch));
+                    return Err(());
+                }
+            }
+        }
+        Err(e) => warn!("Could not get the version of {}: {e}", java.display()),
+    }
     let command_arguments = generate_command_arguments(
         &minecraft_location,
         &instance,
@@ -112,117 +211,276 @@ pub async fn launch(storage: tauri::State<'_, Storage>, instance: Instance) -> R
         version.clone(),
     )
     .await;
-    let version_id = version.id;
-    thread::spawn(move || {
+    let native_root = minecraft_location.get_natives_root(&version.id);
+    Ok(thread::spawn(move || {
         spawn_minecraft_process(
+            java,
             command_arguments,
-            minecraft_location,
+            native_root,
             launch_options,
-            version_id,
             instance,
+            config.launch.export_launch_script,
+            sink,
         )
+    }))
+}
+
+/// Run the user custom command before or after launching the game
+fn run_hook(hook: &str, instance_root: &Path) {
+    if hook.trim().is_empty() {
+        return;
+    }
+    info!("Running {hook}");
+    let mut command = match PLATFORM_INFO.os_family {
+        OsFamily::Windows => {
+            let mut command = Command::new("cmd");
+            command.arg("/C").arg(hook);
+            command
+        }
+        _ => {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(hook);
+            command
+        }
+    };
+    match command.current_dir(instance_root).status() {
+        Ok(status) if status.success() => (),
+        Ok(status) => warn!("Command `{hook}` exits with {status}"),
+        Err(e) => warn!("Could not run `{hook}`: {e}"),
+    }
+}
+
+/// Read the game output line by line, send them to the frontend and save them to the log file
+fn forward_output<R: Read>(
+    output: R,
+    instance_id: Uuid,
+    pid: u32,
+    session_log: Option<SessionLog>,
+    sink: &dyn EventSink,
+) {
+    let mut output = BufReader::new(output);
+    let mut parser = Log4jParser::new();
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match output.read_until(b'\n', &mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => (),
+        }
+        let line = String::from_utf8_lossy(&buf);
+        for line in parser.feed(line.trim_end()) {
+            forward_line(line, instance_id, pid, session_log.as_ref(), sink);
+        }
+    }
+    for line in parser.finish() {
+        forward_line(line, instance_id, pid, session_log.as_ref(), sink);
+    }
+}
+
+fn forward_line(
+    line: LogLine,
+    instance_id: Uuid,
+    pid: u32,
+    session_log: Option<&SessionLog>,
+    sink: &dyn EventSink,
+) {
+    let (content, record) = match line {
+        LogLine::Plain(x) => (x, None),
+        LogLine::Record(record) => (record.to_plain_text(), Some(record)),
+    };
+    trace!("[{}] {}", pid, content);
+    if let Some(session_log) = session_log {
+        session_log.write_line(&content);
+    }
+    if content.to_lowercase().contains("lwjgl version") {
+        process::set_state(instance_id, InstanceState::Running);
+        info!("Found LWJGL version, the game seems to have started successfully.");
+    }
+    sink.log(Log {
+        instance_id,
+        content,
+        level: record.as_ref().and_then(|record| record.level.clone()),
+        record,
     });
-    Ok(())
 }
 
 fn spawn_minecraft_process(
+    java: PathBuf,
     command_arguments: Vec<String>,
-    minecraft_location: MinecraftLocation,
+    native_root: PathBuf,
     launch_options: LaunchOptions,
-    version_id: String,
     instance: Instance,
+    export_script: bool,
+    sink: SharedEventSink,
 ) {
-    let native_root = minecraft_location.get_natives_root(&version_id);
     let instance_root = DATA_LOCATION.get_instance_root(&instance.id);
-    let mut commands = String::new();
-    if PLATFORM_INFO.os_family == OsFamily::Linux {
-        commands.push_str("#!/bin/bash\n\n");
-    }
-    let comment_prefix = if PLATFORM_INFO.os_family == OsFamily::Windows {
-        "::"
-    } else {
-        "#"
-    };
-    commands.push_str(&format!(
-        "{comment_prefix} This file is automatically generated by Conic Launcher.\n"
-    ));
-    commands.push_str(&format!(
-        "{comment_prefix} NOTE: Don't use this file to launch game.\n\n"
-    ));
-    commands.push_str(&format!("cd \"{}\"\n", instance_root.to_string_lossy()));
-    commands.push_str(&format!("{}\n", launch_options.execute_before_launch));
-    if !launch_options.wrap_command.trim().is_empty() {
-        commands.push_str(&format!("{} ", launch_options.wrap_command));
+    if export_script {
+        match export_launch_script(&java, &command_arguments, &launch_options, &instance_root) {
+            Ok(path) => info!("The launch script is exported to {}", path.display()),
+            Err(e) => warn!("Could not export the launch script: {e}"),
+        }
     }
-    // todo(after java exec): add -Dfile.encoding=encoding.name() and other
-    let mut launch_command = "java".to_string();
-    for arg in command_arguments.clone() {
-        launch_command.push(' ');
-        launch_command = format!("{}{}", launch_command, arg);
-    }
-    commands.push_str(&launch_command);
-    if PLATFORM_INFO.os_family == OsFamily::Windows {
-        commands.push_str(&format!("\ndel /F /Q {}\n", native_root.to_string_lossy()))
-    } else {
-        commands.push_str(&format!("\nrm -rf {}\n", native_root.to_string_lossy()))
-    };
-    commands.push_str(&format!("{}\n", launch_options.execute_after_launch));
-    let script_path = match PLATFORM_INFO.os_family {
-        OsFamily::Linux => instance_root.join(".cache").join("launch.sh"),
-        OsFamily::Macos => instance_root.join(".cache").join("launch.sh"),
-        OsFamily::Windows => instance_root.join(".cache").join("launch.bat"),
+    let wrap_command = match shell_words::split(&launch_options.wrap_command) {
+        Ok(x) => x,
+        Err(e) => {
+            error!("Could not parse the wrap command: {e}");
+            process::set_state(instance.id, InstanceState::Exited { code: None });
+            return;
+        }
     };
-
-    std::fs::create_dir_all(script_path.parent().unwrap()).unwrap();
-    std::fs::write(&script_path, commands).unwrap();
-    info!("The startup script is written to {}", script_path.display());
-    let mut minecraft_process = match PLATFORM_INFO.os_family {
-        OsFamily::Windows => std::process::Command::new(script_path),
-        _ => {
-            info!("Running chmod +x {}", script_path.display());
-            let mut chmod = Command::new("chmod");
-            chmod.args(["+x", script_path.to_string_lossy().to_string().as_ref()]);
-            chmod.status().unwrap();
-            let mut command = std::process::Command::new("bash");
-            command.arg(script_path);
+    run_hook(&launch_options.execute_before_launch, &instance_root);
+    let mut command = match wrap_command.split_first() {
+        Some((program, wrap_arguments)) => {
+            let mut command = Command::new(program);
+            command.args(wrap_arguments).arg(&java);
             command
         }
-    }
-    .stdout(Stdio::piped())
-    .spawn()
-    .unwrap();
+        None => Command::new(&java),
+    };
+    // todo(after java exec): add -Dfile.encoding=encoding.name() and other
     info!("Spawning minecraft process");
-    let out = minecraft_process.stdout.take().unwrap();
-    let mut out = std::io::BufReader::new(out);
-    let mut buf = String::new();
-    let pid = minecraft_process.id();
-    while out.read_line(&mut buf).is_ok() {
-        if let Ok(Some(_)) = minecraft_process.try_wait() {
-            break;
+    let start_time = SystemTime::now();
+    let mut minecraft_process = match command
+        .args(&command_arguments)
+        .current_dir(&instance_root)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(x) => x,
+        Err(e) => {
+            error!("Could not spawn minecraft process: {e}");
+            process::set_state(instance.id, InstanceState::Exited { code: None });
+            return;
         }
-        let lines: Vec<_> = buf.split("\n").collect();
-        if let Some(last) = lines.get(lines.len() - 2) {
-            trace!("[{}] {}", pid, last);
-            if last.to_lowercase().contains("lwjgl version") {
-                MAIN_WINDOW.emit("launch_success", instance.id).unwrap();
-                info!("Found LWJGL version, the game seems to have started successfully.");
-            }
-            MAIN_WINDOW
-                .emit(
-                    "log",
-                    Log {
-                        instance_id: instance.id,
-                        content: last.to_string(),
-                    },
-                )
-                .unwrap();
+    };
+    let pid = minecraft_process.id();
+    let stdout = minecraft_process.stdout.take().unwrap();
+    let stderr = minecraft_process.stderr.take().unwrap();
+    let minecraft_process = process::register(instance.id, minecraft_process);
+    let session_log = match SessionLog::create(&instance.id) {
+        Ok(x) => Some(x),
+        Err(e) => {
+            warn!("Could not create the log file: {e}");
+            None
         }
+    };
+    let stderr_thread = {
+        let session_log = session_log.clone();
+        let instance_id = instance.id;
+        let sink = sink.clone();
+        thread::spawn(move || forward_output(stderr, instance_id, pid, session_log, sink.as_ref()))
+    };
+    forward_output(stdout, instance.id, pid, session_log.clone(), sink.as_ref());
+    let _ = stderr_thread.join();
+    if let Some(session_log) = &session_log {
+        session_log.flush();
+        info!("Game output is saved to {}", session_log.path.display());
     }
-    let output = minecraft_process.wait_with_output().unwrap();
-    if !output.status.success() {
-        // TODO: log analysis and remove libraries lock file
-        error!("Minecraft exits with error code {}", output.status);
+    let status = match process::wait(&minecraft_process) {
+        Ok(x) => x,
+        Err(e) => {
+            error!("Could not wait for the minecraft process: {e}");
+            process::set_state(instance.id, InstanceState::Exited { code: None });
+            let _ = std::fs::remove_dir_all(&native_root);
+            return;
+        }
+    };
+    process::set_state(
+        instance.id,
+        InstanceState::Exited {
+            code: status.code(),
+        },
+    );
+    let _ = std::fs::remove_dir_all(&native_root);
+    run_hook(&launch_options.execute_after_launch, &instance_root);
+    if !status.success() {
+        error!("Minecraft exits with error code {}", status);
+        analyze_crash(
+            instance.id,
+            status.code(),
+            &instance_root,
+            start_time,
+            session_log.as_ref(),
+            sink.as_ref(),
+        );
     } else {
-        info!("Minecraft exits with error code {}", output.status);
+        info!("Minecraft exits with error code {}", status);
+    }
+}
+
+/// Find out the causes from the game output and the new crash reports, then notify the frontend
+fn analyze_crash(
+    instance_id: Uuid,
+    exit_code: Option<i32>,
+    instance_root: &Path,
+    start_time: SystemTime,
+    session_log: Option<&SessionLog>,
+    sink: &dyn EventSink,
+) {
+    let mut text = session_log
+        .and_then(|session_log| std::fs::read_to_string(&session_log.path).ok())
+        .unwrap_or_default();
+    let crash_reports = crash::find_crash_reports(instance_root, start_time);
+    for crash_report in &crash_reports {
+        info!("Found crash report {}", crash_report.display());
+        if let Ok(content) = std::fs::read_to_string(crash_report) {
+            text.push('\n');
+            text.push_str(&content);
+        }
+    }
+    let causes = crash::analyze(&text);
+    for cause in &causes {
+        warn!(
+            "Possible crash cause: {:?}, suspect: {}",
+            cause.category,
+            cause.suspect.as_deref().unwrap_or("unknown")
+        );
+    }
+    if causes
+        .iter()
+        .any(|cause| cause.category == CrashCategory::CorruptedLibrary)
+    {
+        info!("Removing libraries lock file, the libraries will be checked next launch");
+        let _ = std::fs::remove_file(instance_root.join(LIBRARIES_LOCK_FILE));
+    }
+    sink.emit(Event::LaunchCrashed(CrashAnalysis {
+        instance_id,
+        exit_code,
+        causes,
+        crash_reports,
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::event::{RecordedEvent, RecordingEventSink};
+
+    use super::*;
+
+    #[test]
+    fn forward_plain_and_xml_output() {
+        let sink = RecordingEventSink::default();
+        let output = r#"[main/INFO]: Loading Minecraft
+<log4j:Event logger="net.minecraft.client.Minecraft" timestamp="1700000000000" level="WARN" thread="Render thread">
+  <log4j:Message><![CDATA[Something is wrong]]></log4j:Message>
+</log4j:Event>
+"#;
+        forward_output(Cursor::new(output), Uuid::nil(), 0, None, &sink);
+        let logs: Vec<Log> = sink
+            .events()
+            .into_iter()
+            .filter_map(|event| match event {
+                RecordedEvent::Log(log) => Some(log),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].content, "[main/INFO]: Loading Minecraft");
+        assert_eq!(logs[0].level, None);
+        assert_eq!(logs[1].content, "[Render thread/WARN]: Something is wrong");
+        assert_eq!(logs[1].level, Some(LogLevel::Warn));
     }
 }
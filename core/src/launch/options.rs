@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use crate::{
-    account::Account,
+    account::{yggdrasil::YggdrasilAgent, Account},
     config::{
         launch::{Server, GC},
         read_config_file,
@@ -26,6 +26,7 @@ pub struct LaunchOptions {
 
     pub(crate) properties: String,
     pub(crate) access_token: String,
+    pub(crate) user_type: String,
 
     /// Min memory, this will add a jvm flag -XMS to the command result
     pub(crate) min_memory: usize,
@@ -66,13 +67,17 @@ pub struct LaunchOptions {
     /// Add other features flags
     pub(crate) extra_enabled_features: Vec<String>,
 
-    // /// TODO: Support yushi's yggdrasil agent <https://github.com/to2mbn/authlib-injector/wiki>
-    // pub(crate) yggdrasil_agent: Option<YggdrasilAgent>,
+    /// Only available for Yggdrasil accounts, prepared by the launcher before launching
+    pub(crate) yggdrasil_agent: Option<YggdrasilAgent>,
+
     pub(crate) gc: GC,
 
     pub(crate) minecraft_location: MinecraftLocation,
     pub(crate) launcher_name: String,
 
+    /// Path of the java executable, `auto` to use the runtime required by the game
+    pub(crate) java_path: String,
+
     pub wrap_command: String,
 
     pub execute_before_launch: String,
@@ -101,11 +106,18 @@ impl LaunchOptions {
                 .launcher_name
                 .clone()
                 .unwrap_or(global_config.launcher_name),
+            java_path: launch_config
+                .java_path
+                .clone()
+                .unwrap_or(global_config.java_path),
             game_profile: GameProfile {
                 name: account.profile.profile_name.clone(),
                 uuid: account.profile.uuid.clone(),
             },
-            access_token: account.access_token.clone().unwrap_or_default(),
+            // Offline accounts have no token, but the game requires one
+            access_token: account.access_token.clone().unwrap_or("0".to_string()),
+            user_type: account.account_type.user_type().to_string(),
+            yggdrasil_agent: None,
             min_memory: launch_config.min_memory.unwrap_or(global_config.min_memory),
             max_memory: launch_config.max_memory.unwrap_or(global_config.max_memory),
             // TODO:
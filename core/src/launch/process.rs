@@ -0,0 +1,196 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Keep track of the running game processes
+
+use std::{
+    collections::HashMap,
+    process::{Child, ExitStatus},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::event::SharedEventSink;
+
+#[derive(Clone, Serialize, PartialEq)]
+pub enum InstanceState {
+    /// The launcher is preparing files or the game is loading
+    Starting,
+    Running,
+    Exited {
+        code: Option<i32>,
+    },
+}
+
+#[derive(Clone, Serialize)]
+pub struct InstanceStatus {
+    pub instance_id: Uuid,
+    pub pid: Option<u32>,
+    /// Unix timestamp in seconds
+    pub start_time: u64,
+    pub state: InstanceState,
+}
+
+struct GameProcess {
+    status: InstanceStatus,
+    child: Option<Arc<Mutex<Child>>>,
+    sink: SharedEventSink,
+}
+
+static PROCESSES: Lazy<Mutex<HashMap<Uuid, GameProcess>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Mark the instance as starting, return `false` if it is already running.
+///
+/// The state changes of the instance are sent to the sink.
+pub fn try_reserve(instance_id: Uuid, sink: SharedEventSink) -> bool {
+    let mut processes = PROCESSES.lock().unwrap();
+    if let Some(process) = processes.get(&instance_id) {
+        if !matches!(process.status.state, InstanceState::Exited { .. }) {
+            return false;
+        }
+    }
+    let status = InstanceStatus {
+        instance_id,
+        pid: None,
+        start_time: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|x| x.as_secs())
+            .unwrap_or_default(),
+        state: InstanceState::Starting,
+    };
+    processes.insert(
+        instance_id,
+        GameProcess {
+            status: status.clone(),
+            child: None,
+            sink: sink.clone(),
+        },
+    );
+    sink.state(status);
+    true
+}
+
+/// Attach the spawned game process to the reserved instance
+pub fn register(instance_id: Uuid, child: Child) -> Arc<Mutex<Child>> {
+    let pid = child.id();
+    let child = Arc::new(Mutex::new(child));
+    let mut processes = PROCESSES.lock().unwrap();
+    if let Some(process) = processes.get_mut(&instance_id) {
+        process.status.pid = Some(pid);
+        process.child = Some(child.clone());
+        process.sink.state(process.status.clone());
+    }
+    child
+}
+
+pub fn set_state(instance_id: Uuid, state: InstanceState) {
+    let mut processes = PROCESSES.lock().unwrap();
+    if let Some(process) = processes.get_mut(&instance_id) {
+        if process.status.state == state {
+            return;
+        }
+        process.status.state = state;
+        if matches!(process.status.state, InstanceState::Exited { .. }) {
+            process.child = None;
+        }
+        process.sink.state(process.status.clone());
+    }
+}
+
+/// Wait for the game process without holding the lock, so it can be killed at the same time
+pub fn wait(child: &Arc<Mutex<Child>>) -> std::io::Result<ExitStatus> {
+    loop {
+        if let Some(status) = child.lock().unwrap().try_wait()? {
+            return Ok(status);
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+}
+
+#[tauri::command]
+pub fn list_running_instances() -> Vec<InstanceStatus> {
+    PROCESSES
+        .lock()
+        .unwrap()
+        .values()
+        .filter(|process| !matches!(process.status.state, InstanceState::Exited { .. }))
+        .map(|process| process.status.clone())
+        .collect()
+}
+
+#[tauri::command]
+pub fn get_instance_status(instance_id: Uuid) -> Option<InstanceStatus> {
+    PROCESSES
+        .lock()
+        .unwrap()
+        .get(&instance_id)
+        .map(|process| process.status.clone())
+}
+
+#[tauri::command]
+pub fn kill_instance(instance_id: Uuid) -> Result<(), ()> {
+    let child = match PROCESSES
+        .lock()
+        .unwrap()
+        .get(&instance_id)
+        .and_then(|process| process.child.clone())
+    {
+        Some(x) => x,
+        None => {
+            warn!("Instance {instance_id} is not running");
+            return Err(());
+        }
+    };
+    info!("Killing instance {instance_id}");
+    let result = child.lock().unwrap().kill();
+    match result {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            warn!("Could not kill instance {instance_id}: {e}");
+            Err(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::event::{RecordedEvent, RecordingEventSink};
+
+    use super::*;
+
+    #[test]
+    fn state_changes_are_sent_to_sink() {
+        let sink = Arc::new(RecordingEventSink::default());
+        let instance_id = Uuid::new_v4();
+        assert!(try_reserve(instance_id, sink.clone()));
+        assert!(!try_reserve(instance_id, sink.clone()));
+        set_state(instance_id, InstanceState::Running);
+        set_state(instance_id, InstanceState::Running);
+        set_state(instance_id, InstanceState::Exited { code: Some(0) });
+        let states: Vec<InstanceState> = sink
+            .events()
+            .into_iter()
+            .filter_map(|event| match event {
+                RecordedEvent::State(status) => Some(status.state),
+                _ => None,
+            })
+            .collect();
+        assert!(
+            states
+                == vec![
+                    InstanceState::Starting,
+                    InstanceState::Running,
+                    InstanceState::Exited { code: Some(0) }
+                ]
+        );
+        assert!(try_reserve(instance_id, sink));
+    }
+}
@@ -0,0 +1,200 @@
+// Conic Launcher
+// Copyright 2022-2026 Broken-Deer and contributors. All rights reserved.
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Export the launch command as a shell script, only for debugging.
+//!
+//! The game is launched directly by the launcher, this script is never executed.
+
+use std::path::{Path, PathBuf};
+
+use crate::{platform::OsFamily, PLATFORM_INFO};
+
+use super::options::LaunchOptions;
+
+/// Quote an argument for `sh` or `cmd`
+pub fn escape_argument(argument: &str) -> String {
+    match PLATFORM_INFO.os_family {
+        OsFamily::Windows => escape_batch_argument(argument),
+        _ => escape_shell_argument(argument),
+    }
+}
+
+fn escape_shell_argument(argument: &str) -> String {
+    shell_words::quote(argument).to_string()
+}
+
+/// Quote the argument the way the MSVC runtime of `java.exe` parses it, then escape every
+/// character special to `cmd`, including the quotes, so `cmd` never enters a quoted section
+fn escape_batch_argument(argument: &str) -> String {
+    let mut escaped = String::new();
+    for c in quote_windows_argument(argument).chars() {
+        match c {
+            // `^%` doesn't work in batch files
+            '%' => escaped.push_str("%%"),
+            '^' | '&' | '|' | '<' | '>' | '(' | ')' | '"' | '!' => {
+                escaped.push('^');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn quote_windows_argument(argument: &str) -> String {
+    if !argument.is_empty() && !argument.contains([' ', '\t', '"']) {
+        return argument.to_string();
+    }
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in argument.chars() {
+        if c == '\\' {
+            backslashes += 1;
+            continue;
+        }
+        // Backslashes are only special before a quote
+        let count = if c == '"' {
+            backslashes * 2 + 1
+        } else {
+            backslashes
+        };
+        quoted.push_str(&"\\".repeat(count));
+        quoted.push(c);
+        backslashes = 0;
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+pub fn generate_launch_script(
+    java: &Path,
+    command_arguments: &[String],
+    launch_options: &LaunchOptions,
+    instance_root: &Path,
+) -> String {
+    let mut script = String::new();
+    let comment_prefix = match PLATFORM_INFO.os_family {
+        OsFamily::Windows => "::",
+        _ => {
+            script.push_str("#!/bin/sh\n\n");
+            "#"
+        }
+    };
+    script.push_str(&format!(
+        "{comment_prefix} This file is automatically generated by Conic Launcher.\n"
+    ));
+    script.push_str(&format!(
+        "{comment_prefix} NOTE: It's only for debugging, the launcher doesn't use it to launch game.\n\n"
+    ));
+    script.push_str(&format!(
+        "cd {}\n",
+        escape_argument(&instance_root.to_string_lossy())
+    ));
+    if !launch_options.execute_before_launch.trim().is_empty() {
+        script.push_str(&format!("{}\n", launch_options.execute_before_launch));
+    }
+    let mut launch_command: Vec<String> =
+        shell_words::split(&launch_options.wrap_command).unwrap_or_default();
+    launch_command.push(java.to_string_lossy().to_string());
+    launch_command.extend(command_arguments.iter().cloned());
+    script.push_str(
+        &launch_command
+            .iter()
+            .map(|argument| escape_argument(argument))
+            .collect::<Vec<_>>()
+            .join(" "),
+    );
+    script.push('\n');
+    if !launch_options.execute_after_launch.trim().is_empty() {
+        script.push_str(&format!("{}\n", launch_options.execute_after_launch));
+    }
+    script
+}
+
+/// Write the script to `.cache/launch.sh` or `.cache/launch.bat` of the instance
+pub fn export_launch_script(
+    java: &Path,
+    command_arguments: &[String],
+    launch_options: &LaunchOptions,
+    instance_root: &Path,
+) -> std::io::Result<PathBuf> {
+    let script_path = match PLATFORM_INFO.os_family {
+        OsFamily::Windows => instance_root.join(".cache").join("launch.bat"),
+        _ => instance_root.join(".cache").join("launch.sh"),
+    };
+    std::fs::create_dir_all(instance_root.join(".cache"))?;
+    std::fs::write(
+        &script_path,
+        generate_launch_script(java, command_arguments, launch_options, instance_root),
+    )?;
+    Ok(script_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARGUMENTS: &[&str] = &[
+        "plain",
+        "with space",
+        "",
+        r#"say "hi""#,
+        "it's",
+        "$HOME",
+        "100%",
+        "a^b",
+        "a&b",
+        r"C:\my dir\",
+        r"C:\dir\",
+    ];
+
+    #[test]
+    fn escape_for_sh() {
+        assert_eq!(escape_shell_argument("plain"), "plain");
+        assert_eq!(escape_shell_argument("with space"), "'with space'");
+        assert_eq!(escape_shell_argument(""), "''");
+        assert_eq!(escape_shell_argument("it's"), r"'it'\''s'");
+        assert_eq!(escape_shell_argument("$HOME"), "'$HOME'");
+        let line = ARGUMENTS
+            .iter()
+            .map(|argument| escape_shell_argument(argument))
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert_eq!(shell_words::split(&line).unwrap(), ARGUMENTS);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn escaped_arguments_survive_sh() {
+        for argument in ARGUMENTS {
+            let output = std::process::Command::new("sh")
+                .arg("-c")
+                .arg(format!("printf %s {}", escape_shell_argument(argument)))
+                .output()
+                .unwrap();
+            assert_eq!(String::from_utf8(output.stdout).unwrap(), *argument);
+        }
+    }
+
+    #[test]
+    fn escape_for_batch() {
+        let expected = [
+            "plain",
+            r#"^"with space^""#,
+            r#"^"^""#,
+            r#"^"say \^"hi\^"^""#,
+            "it's",
+            "$HOME",
+            "100%%",
+            "a^^b",
+            "a^&b",
+            r#"^"C:\my dir\\^""#,
+            r"C:\dir\",
+        ];
+        for (argument, expected) in ARGUMENTS.iter().zip(expected) {
+            assert_eq!(escape_batch_argument(argument), expected, "{argument}");
+        }
+    }
+}
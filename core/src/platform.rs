@@ -34,6 +34,12 @@ pub struct PlatformInfo {
     pub edition: Option<String>,
 }
 
+impl Default for PlatformInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(windows)]
 pub const DELIMITER: &str = ";";
 #[cfg(not(windows))]
@@ -4,3 +4,8 @@
 
 pub mod nbt;
 pub mod unzip;
+
+#[cfg(test)]
+pub mod mock_server;
+#[cfg(test)]
+pub mod temp_dir;
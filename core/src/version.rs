@@ -8,8 +8,8 @@ use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::{collections::HashMap, fs::read_to_string, path::PathBuf, str::FromStr};
-use tauri_plugin_http::reqwest;
 
+use crate::download::mirror;
 use crate::folder::MinecraftLocation;
 
 use crate::PLATFORM_INFO;
@@ -96,7 +96,7 @@ pub struct VersionManifest {
 impl VersionManifest {
     pub async fn new() -> Result<VersionManifest> {
         let response =
-            reqwest::get("https://piston-meta.mojang.com/mc/game/version_manifest_v2.json").await?;
+            mirror::get("https://piston-meta.mojang.com/mc/game/version_manifest_v2.json").await?;
         Ok(response.json::<VersionManifest>().await?)
     }
 }
@@ -111,7 +111,7 @@ pub struct Download {
 #[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AssetIndex {
-    // pub sha1: String,
+    pub sha1: Option<String>,
     pub size: u64,
     pub url: String,
     pub id: String,
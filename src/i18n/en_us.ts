@@ -158,6 +158,11 @@ export default {
             maxDownloadSpeedDesc: "Set the limit of download speed in B/s. 0 for no limit.",
             mirrorServer: "Mirror Server",
             mirrorServerDesc: "Download game files from mirror server.",
+            cache: "Cache",
+            sharedCache: "Shared Cache",
+            sharedCacheDesc: "Share the downloaded game files between data folders, so they are only downloaded once.",
+            cleanCache: "Clean Cache",
+            cleanCacheDesc: "Remove the cached files no longer used by any data folder.",
             proxy: "Proxy",
             useSystemProxy: "Use System Proxy",
         },
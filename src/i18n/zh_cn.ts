@@ -151,9 +151,14 @@ export default {
                 "限制同时与服务器建立的连接数量，只对安装时的下载有效，<strong>过大反而会降低速度！</strong>",
             maxDownloadSpeed: "最大下载速度(单位：B/s)",
             maxDownloadSpeedDesc:
-                "限制下载速度，只对安装时的下载有效，必须大于1024才有效，设置为 0 以禁用",
+                "限制下载速度，只对安装时的下载有效，设置为 0 以禁用",
             mirrorServer: "镜像服务器",
             mirrorServerDesc: "从其他地方下载游戏文件",
+            cache: "缓存",
+            sharedCache: "共享缓存",
+            sharedCacheDesc: "在不同的数据文件夹之间共享下载的游戏文件，相同的文件只下载一次",
+            cleanCache: "清理缓存",
+            cleanCacheDesc: "删除不再被任何数据文件夹使用的缓存文件",
             proxy: "代理",
             useSystemProxy: "使用系统代理",
         },
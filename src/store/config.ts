@@ -24,6 +24,9 @@ export type Config = {
     download: {
         max_connection: number
         max_download_speed: number
+        host_speed_limits: Record<string, number>
+        shared_cache: boolean
+        mirrors: ({ type: "Official" } | { type: "Bmclapi" } | { type: "Custom"; prefix: string })[]
     }
     launch: {
         min_memory: number
@@ -43,11 +46,13 @@ export type Config = {
         extra_class_paths: string
         gc: "Serial" | "Parallel" | "ParallelOld" | "G1" | "Z"
         launcher_name: string
+        java_path: string
         wrap_command: string
         execute_before_launch: string
         execute_after_launch: string
         skip_refresh_account: boolean
         skip_check_files: boolean
+        export_launch_script: boolean
     }
 }
 
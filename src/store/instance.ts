@@ -32,6 +32,7 @@ export type Instance = {
             extra_class_paths?: string
             gc?: "Serial" | "Parallel" | "ParallelOld" | "G1" | "Z"
             launcher_name?: string
+            java_path?: string
             wrap_command?: string
             execute_before_launch?: string
             execute_after_launch?: string